@@ -0,0 +1,39 @@
+//! Benchmarks the per-line bookkeeping `GlobalAsmBlock` does while parsing a
+//! `GLOBAL_ASM` block, using a corpus shaped like a large decomp tree: many
+//! `.text` instructions interleaved with `.late_rodata` floats/doubles.
+use asm_processor::asm::block::GlobalAsmBlock;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+fn text_and_late_rodata_corpus(num_instrs: usize) -> Vec<String> {
+    let mut lines = vec!["glabel func_80123456".to_string()];
+    for i in 0..num_instrs {
+        lines.push(format!("addiu $t{}, $t{}, {}", i % 8, i % 8, i));
+    }
+    lines.push(".section .late_rodata".to_string());
+    lines.push(".late_rodata_alignment 8".to_string());
+    for i in 0..(num_instrs / 8) {
+        if i % 2 == 0 {
+            lines.push(format!(".float {}.5", i));
+        } else {
+            lines.push(format!(".double {}.5", i));
+        }
+    }
+    lines
+}
+
+fn bench_process_line(c: &mut Criterion) {
+    let corpus = text_and_late_rodata_corpus(5000);
+
+    c.bench_function("process_line/text_and_late_rodata", |b| {
+        b.iter(|| {
+            let mut block = GlobalAsmBlock::new("bench fn");
+            for line in &corpus {
+                block.process_line(black_box(line), "latin1").unwrap();
+            }
+            black_box(&block);
+        });
+    });
+}
+
+criterion_group!(benches, bench_process_line);
+criterion_main!(benches);
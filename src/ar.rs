@@ -0,0 +1,265 @@
+use thiserror::Error;
+
+use crate::elf::constants::{SHN_UNDEF, STB_LOCAL};
+use crate::elf::file::ElfFile;
+
+const AR_MAGIC: &[u8] = b"!<arch>\n";
+const HEADER_SIZE: usize = 60;
+const END_MAGIC: &[u8] = b"`\n";
+
+#[derive(Error, Debug)]
+pub enum ArError {
+    #[error("not an ar archive (bad magic)")]
+    BadMagic,
+    #[error("truncated member header")]
+    TruncatedHeader,
+    #[error("malformed member header {0} field")]
+    MalformedHeader(&'static str),
+    #[error("member header missing terminator")]
+    MissingTerminator,
+    #[error("long-name table reference out of range: {0}")]
+    LongNameOutOfRange(String),
+}
+
+pub type Result<T> = std::result::Result<T, ArError>;
+
+/// One real member (an object file, usually) of an ar archive, with its
+/// GNU long name already resolved. The `//` long-name table and `/` symbol
+/// index bookkeeping members are not exposed here - [`Archive::parse`]
+/// consumes the former to resolve names and discards the latter, since
+/// [`Archive::to_bytes`] always regenerates it from the current members.
+#[derive(Debug, Clone)]
+pub struct ArMember {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+/// A parsed System-V/GNU `ar` archive: the `!<arch>\n` magic followed by a
+/// flat list of 60-byte member headers (name/size, 2-byte aligned bodies),
+/// used to bundle several `.o` files into a single `.a` for the linker.
+///
+/// Member order is preserved across a parse/`to_bytes` round trip; the `/`
+/// symbol index is rebuilt from whatever global symbols the members define
+/// at write time, since callers are expected to rewrite member bodies (e.g.
+/// via [`crate::objfile::fixup_archive`]) in between.
+#[derive(Debug, Clone, Default)]
+pub struct Archive {
+    pub members: Vec<ArMember>,
+}
+
+impl Archive {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < AR_MAGIC.len() || &data[..AR_MAGIC.len()] != AR_MAGIC {
+            return Err(ArError::BadMagic);
+        }
+
+        let mut long_names: Vec<u8> = Vec::new();
+        let mut members = Vec::new();
+        let mut offset = AR_MAGIC.len();
+
+        while offset < data.len() {
+            if offset + HEADER_SIZE > data.len() {
+                return Err(ArError::TruncatedHeader);
+            }
+            let header = &data[offset..offset + HEADER_SIZE];
+            if &header[58..60] != END_MAGIC {
+                return Err(ArError::MissingTerminator);
+            }
+
+            let raw_name = String::from_utf8_lossy(&header[0..16]).trim_end().to_string();
+            let size: usize = std::str::from_utf8(&header[48..58])
+                .map_err(|_| ArError::MalformedHeader("size"))?
+                .trim()
+                .parse()
+                .map_err(|_| ArError::MalformedHeader("size"))?;
+
+            let body_start = offset + HEADER_SIZE;
+            let body = data
+                .get(body_start..body_start + size)
+                .ok_or(ArError::TruncatedHeader)?
+                .to_vec();
+
+            if raw_name == "//" {
+                long_names = body;
+            } else if raw_name == "/" {
+                // Symbol index; regenerated by `to_bytes`, nothing to keep.
+            } else if let Some(name_offset) = raw_name.strip_prefix('/') {
+                let name_offset: usize = name_offset
+                    .parse()
+                    .map_err(|_| ArError::LongNameOutOfRange(raw_name.clone()))?;
+                let rest = long_names
+                    .get(name_offset..)
+                    .ok_or_else(|| ArError::LongNameOutOfRange(raw_name.clone()))?;
+                let len = rest.iter().position(|&b| b == b'/' || b == b'\n').unwrap_or(rest.len());
+                members.push(ArMember {
+                    name: String::from_utf8_lossy(&rest[..len]).into_owned(),
+                    data: body,
+                });
+            } else {
+                members.push(ArMember {
+                    name: raw_name.strip_suffix('/').unwrap_or(&raw_name).to_string(),
+                    data: body,
+                });
+            }
+
+            offset = body_start + size;
+            if size % 2 == 1 {
+                offset += 1; // members are 2-byte aligned
+            }
+        }
+
+        Ok(Self { members })
+    }
+
+    /// Re-serialize, regenerating the `//` long-name table (for any name
+    /// over 15 bytes) and the `/` symbol index - a GNU-style table of every
+    /// defined, non-local symbol in every ELF member, paired with the
+    /// archive offset of the member that defines it.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut long_names = Vec::new();
+        let name_fields: Vec<[u8; 16]> = self
+            .members
+            .iter()
+            .map(|m| name_field(&m.name, &mut long_names))
+            .collect();
+        if long_names.len() % 2 == 1 {
+            long_names.push(b'\n');
+        }
+
+        let symbols = collect_symbols(&self.members);
+        let symtab_size = 4 + symbols.len() * 4 + symbols.iter().map(|(name, _)| name.len() + 1).sum::<usize>();
+        let symtab_padded = symtab_size + (symtab_size % 2);
+
+        let mut offset = AR_MAGIC.len() + HEADER_SIZE + symtab_padded;
+        if !long_names.is_empty() {
+            offset += HEADER_SIZE + long_names.len();
+        }
+        let member_offsets: Vec<usize> = self
+            .members
+            .iter()
+            .map(|m| {
+                let this_offset = offset;
+                offset += HEADER_SIZE + m.data.len() + (m.data.len() % 2);
+                this_offset
+            })
+            .collect();
+
+        let mut symtab_data = Vec::with_capacity(symtab_size);
+        symtab_data.extend_from_slice(&(symbols.len() as u32).to_be_bytes());
+        for &(_, member_index) in &symbols {
+            symtab_data.extend_from_slice(&(member_offsets[member_index] as u32).to_be_bytes());
+        }
+        for (name, _) in &symbols {
+            symtab_data.extend_from_slice(name.as_bytes());
+            symtab_data.push(0);
+        }
+
+        let mut out = Vec::new();
+        out.extend_from_slice(AR_MAGIC);
+        write_member(&mut out, name_field("/", &mut Vec::new()), &symtab_data);
+        if !long_names.is_empty() {
+            write_member(&mut out, name_field("//", &mut Vec::new()), &long_names);
+        }
+        for (member, field) in self.members.iter().zip(&name_fields) {
+            write_member(&mut out, *field, &member.data);
+        }
+
+        out
+    }
+}
+
+/// Compute this member's 16-byte header name field, appending it to
+/// `long_names` (and returning a `/<offset>` reference) if it's too long to
+/// store inline.
+fn name_field(name: &str, long_names: &mut Vec<u8>) -> [u8; 16] {
+    let mut field = [b' '; 16];
+    if name == "/" || name == "//" {
+        // The symbol-index and long-name-table members are named literally,
+        // not via the usual trailing-`/` or long-name-table encoding.
+        field[..name.len()].copy_from_slice(name.as_bytes());
+    } else if name.len() <= 15 && !name.contains('/') {
+        field[..name.len()].copy_from_slice(name.as_bytes());
+        field[name.len()] = b'/';
+    } else {
+        let marker = format!("/{}", long_names.len());
+        field[..marker.len()].copy_from_slice(marker.as_bytes());
+        long_names.extend_from_slice(name.as_bytes());
+        long_names.push(b'/');
+        long_names.push(b'\n');
+    }
+    field
+}
+
+/// Every defined, non-local symbol in every ELF member, in member order,
+/// paired with that member's index. Members that aren't parseable ELF
+/// (shouldn't happen for the `.o` members we care about) contribute none.
+fn collect_symbols(members: &[ArMember]) -> Vec<(String, usize)> {
+    let mut symbols = Vec::new();
+    for (index, member) in members.iter().enumerate() {
+        let Ok(elf) = ElfFile::new(&member.data) else { continue };
+        for symbol in &elf.sections[elf.symtab].symbols {
+            if symbol.bind != STB_LOCAL && symbol.st_shndx != SHN_UNDEF && !symbol.name.is_empty() {
+                symbols.push((symbol.name.clone(), index));
+            }
+        }
+    }
+    symbols
+}
+
+fn write_member(out: &mut Vec<u8>, name_field: [u8; 16], data: &[u8]) {
+    out.extend_from_slice(&name_field);
+    out.extend_from_slice(b"0           "); // mtime (12)
+    out.extend_from_slice(b"0     "); // uid (6)
+    out.extend_from_slice(b"0     "); // gid (6)
+    out.extend_from_slice(b"100644  "); // mode (8)
+    out.extend_from_slice(format!("{:<10}", data.len()).as_bytes());
+    out.extend_from_slice(END_MAGIC);
+    out.extend_from_slice(data);
+    if data.len() % 2 == 1 {
+        out.push(b'\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip_short_names() {
+        let archive = Archive {
+            members: vec![
+                ArMember { name: "a.o".to_string(), data: vec![1, 2, 3] },
+                ArMember { name: "b.o".to_string(), data: vec![4, 5] },
+            ],
+        };
+
+        let bytes = archive.to_bytes();
+        let parsed = Archive::parse(&bytes).unwrap();
+
+        assert_eq!(parsed.members.len(), 2);
+        assert_eq!(parsed.members[0].name, "a.o");
+        assert_eq!(parsed.members[0].data, vec![1, 2, 3]);
+        assert_eq!(parsed.members[1].name, "b.o");
+        assert_eq!(parsed.members[1].data, vec![4, 5]);
+    }
+
+    #[test]
+    fn test_roundtrip_long_name() {
+        let long_name = "a_member_name_longer_than_fifteen_bytes.o".to_string();
+        let archive = Archive {
+            members: vec![ArMember { name: long_name.clone(), data: vec![0xaa; 5] }],
+        };
+
+        let bytes = archive.to_bytes();
+        let parsed = Archive::parse(&bytes).unwrap();
+
+        assert_eq!(parsed.members.len(), 1);
+        assert_eq!(parsed.members[0].name, long_name);
+        assert_eq!(parsed.members[0].data, vec![0xaa; 5]);
+    }
+
+    #[test]
+    fn test_bad_magic_rejected() {
+        assert!(matches!(Archive::parse(b"not an archive"), Err(ArError::BadMagic)));
+    }
+}
@@ -0,0 +1,201 @@
+use crate::elf::constants::{
+    R_MIPS_32, R_MIPS_26, R_MIPS_HI16, R_MIPS_LO16,
+    R_PPC_ADDR32, R_PPC_ADDR24, R_PPC_ADDR16, R_PPC_ADDR16_HI, R_PPC_ADDR16_HA,
+    R_PPC_REL24, R_PPC_REL14,
+};
+use crate::utils::error::{Error, Result};
+
+/// Instruction-count thresholds used by [`crate::processor::parse_source`] to decide
+/// how many instructions a `GLOBAL_ASM` function needs before the late-rodata
+/// workaround kicks in, and how many to skip when looking for its end.
+#[derive(Debug, Clone, Copy)]
+pub struct InstrCounts {
+    pub min_instr_count: usize,
+    pub skip_instr_count: usize,
+    pub prelude_if_late_rodata: usize,
+}
+
+/// A target instruction set and toolchain convention that the preprocessor can
+/// emit for, selected on the command line with `--arch`.
+///
+/// `asm-processor` started out hardcoded to MIPS (N64 decomp projects); this
+/// trait carries the numbers that used to live directly in `parse_source`, so
+/// other architectures can be supported by adding a profile instead of forking
+/// the core loop.
+pub trait Arch {
+    /// Name used on the command line (`--arch <name>`).
+    fn name(&self) -> &'static str;
+
+    /// Min/skip instruction counts and late-rodata prelude size for a given
+    /// `-O0`/`-O1`/`-O2`/`-g`/`-g3` optimization level, frame-pointer and KPIC setting.
+    fn instr_counts(&self, opt: &str, framepointer: bool, kpic: bool) -> Result<InstrCounts>;
+
+    /// Whether jump tables compiled at this optimization level are placed in
+    /// `.rodata` directly, as opposed to needing the late-rodata workaround.
+    fn use_jtbl_for_rodata(&self, opt: &str, framepointer: bool, kpic: bool) -> bool;
+
+    /// Assembler invocation used when `--assembler` isn't passed explicitly.
+    fn default_assembler(&self) -> &'static str;
+
+    /// Relocation types this architecture's objfile fixup understands. Anything
+    /// else encountered in a `.rel`/`.rela` section is reported as an error
+    /// instead of being silently copied through.
+    fn relocation_types(&self) -> &'static [(&'static str, u32)];
+
+    /// Whether objects for this architecture carry a `.reginfo` section
+    /// (the MIPS o32 ABI register-usage summary) that needs merging between
+    /// the original and assembled-GLOBAL_ASM object files during fixup.
+    fn has_reginfo(&self) -> bool;
+
+    /// Whether this architecture's compiler emits an SGI-style `.mdebug`
+    /// symbolic debug section, which `fixup_objfile` mines for statics that
+    /// never made it into the ELF symbol table.
+    fn has_mdebug(&self) -> bool;
+
+    /// Assembly mnemonic used to pad a gap left in `.text` by a function
+    /// whose compiled size changed, one instruction-width word at a time.
+    fn text_pad_mnemonic(&self) -> &'static str;
+}
+
+/// MIPS (the original N64/PSX decomp target). Default profile.
+pub struct Mips;
+
+impl Arch for Mips {
+    fn name(&self) -> &'static str {
+        "mips"
+    }
+
+    fn instr_counts(&self, opt: &str, framepointer: bool, kpic: bool) -> Result<InstrCounts> {
+        let (min_instr_count, skip_instr_count, prelude_if_late_rodata) = match (opt, framepointer) {
+            ("O1" | "O2", true) => (6, 5, 0),
+            ("O1" | "O2", false) => (2, 1, 0),
+            ("O0", true) => (8, 8, 0),
+            ("O0", false) => (4, 4, 0),
+            ("g", true) => (7, 7, 0),
+            ("g", false) => (4, 4, 0),
+            ("g3", true) => (4, 4, 0),
+            ("g3", false) => (2, 2, 0),
+            _ => return Err(Error::InvalidInput("must pass one of -g, -O0, -O1, -O2, -O2 -g3".into())),
+        };
+
+        Ok(if kpic {
+            if opt == "g3" || opt == "O2" {
+                InstrCounts { min_instr_count, skip_instr_count, prelude_if_late_rodata: 3 }
+            } else {
+                InstrCounts {
+                    min_instr_count: min_instr_count + 3,
+                    skip_instr_count: skip_instr_count + 3,
+                    prelude_if_late_rodata,
+                }
+            }
+        } else {
+            InstrCounts { min_instr_count, skip_instr_count, prelude_if_late_rodata }
+        })
+    }
+
+    fn use_jtbl_for_rodata(&self, opt: &str, framepointer: bool, kpic: bool) -> bool {
+        matches!(opt, "O2" | "g3") && !framepointer && !kpic
+    }
+
+    fn default_assembler(&self) -> &'static str {
+        "mips-linux-gnu-as -march=vr4300 -mabi=32"
+    }
+
+    fn relocation_types(&self) -> &'static [(&'static str, u32)] {
+        &[
+            ("R_MIPS_32", R_MIPS_32),
+            ("R_MIPS_26", R_MIPS_26),
+            ("R_MIPS_HI16", R_MIPS_HI16),
+            ("R_MIPS_LO16", R_MIPS_LO16),
+        ]
+    }
+
+    fn has_reginfo(&self) -> bool {
+        true
+    }
+
+    fn has_mdebug(&self) -> bool {
+        true
+    }
+
+    fn text_pad_mnemonic(&self) -> &'static str {
+        "nop"
+    }
+}
+
+/// PowerPC EABI, as used by GameCube/Wii (Gekko/Broadway) decomp projects.
+pub struct PowerPc;
+
+impl Arch for PowerPc {
+    fn name(&self) -> &'static str {
+        "ppc"
+    }
+
+    fn instr_counts(&self, opt: &str, framepointer: bool, _kpic: bool) -> Result<InstrCounts> {
+        // PowerPC EABI has no KPIC mode, so unlike Mips::instr_counts this
+        // ignores `kpic` rather than padding the prelude for it.
+        let (min_instr_count, skip_instr_count) = match (opt, framepointer) {
+            ("O1" | "O2", true) => (5, 4),
+            ("O1" | "O2", false) => (2, 1),
+            ("O0", true) => (7, 7),
+            ("O0", false) => (4, 4),
+            ("g", true) => (6, 6),
+            ("g", false) => (4, 4),
+            ("g3", true) => (4, 4),
+            ("g3", false) => (2, 2),
+            _ => return Err(Error::InvalidInput("must pass one of -g, -O0, -O1, -O2, -O2 -g3".into())),
+        };
+
+        Ok(InstrCounts { min_instr_count, skip_instr_count, prelude_if_late_rodata: 0 })
+    }
+
+    fn use_jtbl_for_rodata(&self, opt: &str, framepointer: bool, _kpic: bool) -> bool {
+        matches!(opt, "O2" | "g3") && !framepointer
+    }
+
+    fn default_assembler(&self) -> &'static str {
+        "powerpc-eabi-as -mgekko"
+    }
+
+    fn relocation_types(&self) -> &'static [(&'static str, u32)] {
+        &[
+            ("R_PPC_ADDR32", R_PPC_ADDR32),
+            ("R_PPC_ADDR24", R_PPC_ADDR24),
+            ("R_PPC_ADDR16", R_PPC_ADDR16),
+            ("R_PPC_ADDR16_HI", R_PPC_ADDR16_HI),
+            ("R_PPC_ADDR16_HA", R_PPC_ADDR16_HA),
+            ("R_PPC_REL24", R_PPC_REL24),
+            ("R_PPC_REL14", R_PPC_REL14),
+        ]
+    }
+
+    fn has_reginfo(&self) -> bool {
+        // PowerPC EABI objects carry no MIPS o32-style register-usage section.
+        false
+    }
+
+    fn has_mdebug(&self) -> bool {
+        // PowerPC EABI compilers emit DWARF `.debug_info`, not an SGI-style
+        // `.mdebug` symbol table; static-symbol recovery from debug info for
+        // this backend isn't implemented yet.
+        false
+    }
+
+    fn text_pad_mnemonic(&self) -> &'static str {
+        // Spelled out as a raw word rather than relying on `nop` being a
+        // recognized pseudo-op in every PowerPC assembler.
+        ".long 0"
+    }
+}
+
+/// Resolve the profile selected by `--arch <name>`.
+pub fn from_name(name: &str) -> Result<Box<dyn Arch>> {
+    match name {
+        "mips" => Ok(Box::new(Mips)),
+        "ppc" | "powerpc" => Ok(Box::new(PowerPc)),
+        other => Err(Error::InvalidInput(format!(
+            "unknown --arch '{}' (expected \"mips\" or \"ppc\")",
+            other
+        ))),
+    }
+}
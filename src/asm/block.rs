@@ -1,38 +1,154 @@
 use std::collections::HashMap;
+use std::str::FromStr;
 use crate::utils::error::{Error, Result};
 use crate::utils::state::GlobalState;
 use crate::asm::function::Function;
 use crate::utils::constants::MAX_FN_SIZE;
-use lazy_static::lazy_static;
-use regex::Regex;
-use encoding_rs::Encoding;
+use crate::asm::lexer::{tokenize, tokenize_collecting, Token};
 use std::convert::TryFrom;
 
-lazy_static! {
-    static ref RE_COMMENT_OR_STRING: Regex = Regex::new(
-        r#"#.*|/\*.*?\*/|"(?:\\.|[^\\"])*""#
-    ).unwrap();
+/// A section a `GLOBAL_ASM` block can assemble into, tracked separately in
+/// [`GlobalAsmBlock::fn_section_sizes`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    Text,
+    Data,
+    Rodata,
+    LateRodata,
+    Bss,
 }
 
-fn re_comment_replacer(cap: &regex::Captures) -> String {
-    let s = cap.get(0).unwrap().as_str();
-    if s.starts_with('#') || s.starts_with('/') {
-        " ".to_string()
-    } else {
-        s.to_string()
+impl Section {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Section::Text => ".text",
+            Section::Data => ".data",
+            Section::Rodata => ".rodata",
+            Section::LateRodata => ".late_rodata",
+            Section::Bss => ".bss",
+        }
+    }
+
+    /// Whether a chunk added to this section must be word-sized, as is the
+    /// case for anything that ends up interleaved with instructions.
+    fn requires_word_multiple(self) -> bool {
+        match self {
+            Section::Text | Section::LateRodata => true,
+            Section::Data | Section::Rodata | Section::Bss => false,
+        }
+    }
+
+    /// Index into [`GlobalAsmBlock::fn_section_sizes`]'s fixed-size array.
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl FromStr for Section {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Self, ()> {
+        match s {
+            ".text" => Ok(Section::Text),
+            ".data" => Ok(Section::Data),
+            ".rodata" | ".rdata" => Ok(Section::Rodata),
+            ".late_rodata" => Ok(Section::LateRodata),
+            ".bss" => Ok(Section::Bss),
+            _ => Err(()),
+        }
     }
 }
 
+/// A `.`-prefixed assembler directive recognized within a `GLOBAL_ASM` block,
+/// aliases included (e.g. `.4byte` is a `Word`, `.zero` is a `Space`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Directive {
+    /// `.section NAME`
+    Section,
+    /// A bare section name used as a directive, e.g. a lone `.text` line.
+    BareSection(Section),
+    LateRodataAlignment,
+    Incbin,
+    Include,
+    Word,
+    Double,
+    Space,
+    Balign,
+    Align,
+    Ascii,
+    Asciz,
+    Byte,
+    Half,
+    Size,
+    Fill,
+}
+
+impl FromStr for Directive {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Self, ()> {
+        if let Ok(section) = s.parse::<Section>() {
+            return Ok(Directive::BareSection(section));
+        }
+        match s {
+            ".section" => Ok(Directive::Section),
+            ".late_rodata_alignment" => Ok(Directive::LateRodataAlignment),
+            ".incbin" => Ok(Directive::Incbin),
+            ".include" => Ok(Directive::Include),
+            ".word" | ".gpword" | ".float" | ".4byte" => Ok(Directive::Word),
+            ".double" => Ok(Directive::Double),
+            ".space" | ".zero" => Ok(Directive::Space),
+            ".balign" => Ok(Directive::Balign),
+            ".align" => Ok(Directive::Align),
+            ".ascii" => Ok(Directive::Ascii),
+            ".asciz" | ".asciiz" => Ok(Directive::Asciz),
+            ".byte" => Ok(Directive::Byte),
+            ".half" | ".hword" | ".short" | ".2byte" => Ok(Directive::Half),
+            ".size" => Ok(Directive::Size),
+            ".fill" => Ok(Directive::Fill),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Severity of a [`Diagnostic`] produced by the collecting variants of
+/// [`GlobalAsmBlock`]'s parsing methods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A single non-fatal problem found while processing a `GLOBAL_ASM` block.
+///
+/// Produced by [`GlobalAsmBlock::process_line_collecting`] and
+/// [`GlobalAsmBlock::finish_collecting`], the non-fatal counterparts of
+/// [`GlobalAsmBlock::process_line`] and [`GlobalAsmBlock::finish`] meant for
+/// editor/LSP use: rather than aborting at the first problem, every one
+/// found is recorded here so they can all be surfaced inline at once.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    /// 1-based line number within the `GLOBAL_ASM` block (see `num_lines`).
+    pub line: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct GlobalAsmBlock {
     pub fn_desc: String,
-    pub cur_section: String,
+    pub cur_section: Section,
     pub asm_conts: Vec<String>,
     pub late_rodata_asm_conts: Vec<String>,
     pub late_rodata_alignment: usize,
     pub late_rodata_alignment_from_content: bool,
     pub text_glabels: Vec<String>,
-    pub fn_section_sizes: HashMap<String, usize>,
+    pub fn_section_sizes: [usize; 5],
+    /// Quoted paths (unescaped, still relative to whatever file this block
+    /// itself lives in) pulled from `.incbin`/`.include` directives, for the
+    /// caller to resolve against that file's directory and fold into the
+    /// `.d` file's dependency list.
+    pub incbin_deps: Vec<String>,
     fn_ins_inds: Vec<(usize, usize)>,
     glued_line: String,
     num_lines: usize,
@@ -42,25 +158,39 @@ impl GlobalAsmBlock {
     pub fn new(fn_desc: &str) -> Self {
         Self {
             fn_desc: fn_desc.to_string(),
-            cur_section: ".text".to_string(),
+            cur_section: Section::Text,
             asm_conts: Vec::new(),
             late_rodata_asm_conts: Vec::new(),
             late_rodata_alignment: 0,
             late_rodata_alignment_from_content: false,
             text_glabels: Vec::new(),
-            fn_section_sizes: HashMap::from([
-                (".text".to_string(), 0),
-                (".data".to_string(), 0),
-                (".bss".to_string(), 0),
-                (".rodata".to_string(), 0),
-                (".late_rodata".to_string(), 0),
-            ]),
+            fn_section_sizes: [0; 5],
+            incbin_deps: Vec::new(),
             fn_ins_inds: Vec::new(),
             glued_line: String::new(),
             num_lines: 0,
         }
     }
 
+    /// Strip the surrounding quotes from a `"..."` operand and resolve
+    /// backslash escapes, for pulling a usable path out of a `.incbin`/
+    /// `.include` directive's filename argument.
+    fn unquote_path(literal: &str) -> Option<String> {
+        let inner = literal.trim().strip_prefix('"')?.strip_suffix('"')?;
+        let mut out = String::with_capacity(inner.len());
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            if c == '\\' {
+                if let Some(next) = chars.next() {
+                    out.push(next);
+                }
+            } else {
+                out.push(c);
+            }
+        }
+        Some(out)
+    }
+
     fn fail(&self, message: &str, line: Option<&str>) -> Error {
         let context = if let Some(line_str) = line {
             format!("{}, at line \"{}\"", self.fn_desc, line_str)
@@ -70,102 +200,43 @@ impl GlobalAsmBlock {
         Error::AssemblyProcessing(format!("{}\nwithin {}", message, context))
     }
 
-    fn count_quoted_size(&self, line: &str, z: bool, real_line: &str, output_enc: &str) -> Result<usize> {
-        // Handle output encoding properly
-        let enc = Encoding::for_label(output_enc)
-            .ok_or_else(|| Error::AssemblyProcessing("Invalid encoding".into()))?;
-        let (encoded, _, _) = enc.encode(line);
-        let line = String::from_utf8_lossy(&encoded);
-        
-        let mut in_quote = false;
-        let mut has_comma = true;
-        let mut num_parts = 0;
-        let mut ret = 0;
-        let mut i = 0;
-        let chars: Vec<char> = line.chars().collect();
-        let digits = "0123456789";
-
-        while i < chars.len() {
-            let c = chars[i];
-            i += 1;
-            if !in_quote {
-                if c == '"' {
-                    in_quote = true;
-                    if z && !has_comma {
-                        return Err(self.fail(".asciiz with glued strings is not supported due to GNU as version diffs", Some(real_line)));
-                    }
-                    num_parts += 1;
-                } else if c == ',' {
-                    has_comma = true;
-                }
-            } else {
-                if c == '"' {
-                    in_quote = false;
-                    has_comma = false;
-                    continue;
-                }
-                ret += 1;
-                if c != '\\' {
-                    continue;
-                }
-                if i == chars.len() {
-                    return Err(self.fail("backslash at end of line not supported", Some(real_line)));
-                }
-                let c = chars[i];
-                i += 1;
-                if c == 'x' {
-                    while i < chars.len() && (digits.contains(chars[i]) || "abcdefABCDEF".contains(chars[i])) {
-                        i += 1;
-                    }
-                } else if digits.contains(c) {
-                    let mut it = 0;
-                    while i < chars.len() && digits.contains(chars[i]) && it < 2 {
-                        i += 1;
-                        it += 1;
-                    }
-                }
-            }
-        }
-
-        if in_quote {
-            return Err(self.fail("unterminated string literal", Some(real_line)));
-        }
-        if num_parts == 0 {
-            return Err(self.fail(".ascii with no string", Some(real_line)));
-        }
-        Ok(if z { ret + num_parts } else { ret })
+    /// Build a [`Diagnostic`] at the current line, mirroring [`Self::fail`]'s
+    /// message formatting but without aborting.
+    fn diag(&self, severity: Severity, message: &str, line: Option<&str>) -> Diagnostic {
+        let message = match line {
+            Some(line_str) => format!("{}, at line \"{}\"", message, line_str),
+            None => message.to_string(),
+        };
+        Diagnostic { line: self.num_lines, severity, message }
     }
 
     fn align2(&mut self) {
-        let section = self.cur_section.clone();
-        let size = self.fn_section_sizes.get_mut(&section).unwrap();
-        while *size % 2 != 0 {
-            *size += 1;
-        }
+        self.align_to(2);
     }
 
     fn align4(&mut self) {
-        let section = self.cur_section.clone();
-        let size = self.fn_section_sizes.get_mut(&section).unwrap();
-        while *size % 4 != 0 {
+        self.align_to(4);
+    }
+
+    /// Pad the current section's size up to the next multiple of `alignment`.
+    fn align_to(&mut self, alignment: usize) {
+        let size = &mut self.fn_section_sizes[self.cur_section.index()];
+        while *size % alignment != 0 {
             *size += 1;
         }
     }
 
     fn add_sized(&mut self, size: isize, line: &str) -> Result<()> {
-        if self.cur_section == ".text" || self.cur_section == ".late_rodata" {
-            if size % 4 != 0 {
-                return Err(self.fail("size must be a multiple of 4", Some(line)));
-            }
+        if self.cur_section.requires_word_multiple() && size % 4 != 0 {
+            return Err(self.fail("size must be a multiple of 4", Some(line)));
         }
         if size < 0 {
             return Err(self.fail("size cannot be negative", Some(line)));
         }
 
-        let section = self.cur_section.clone();
-        *self.fn_section_sizes.get_mut(&section).unwrap() += size as usize;
-        
-        if self.cur_section == ".text" {
+        self.fn_section_sizes[self.cur_section.index()] += size as usize;
+
+        if self.cur_section == Section::Text {
             if self.text_glabels.is_empty() {
                 return Err(self.fail(".text block without an initial glabel", Some(line)));
             }
@@ -174,150 +245,416 @@ impl GlobalAsmBlock {
         Ok(())
     }
 
+    /// Non-fatal counterpart of [`Self::add_sized`]: records every problem
+    /// found as a [`Diagnostic`] instead of aborting. A negative size can't
+    /// be meaningfully added to the section total, so it's skipped; any
+    /// other problem is recorded but the size is still added.
+    fn add_sized_collecting(&mut self, size: isize, line: &str, diags: &mut Vec<Diagnostic>) {
+        if self.cur_section.requires_word_multiple() && size % 4 != 0 {
+            diags.push(self.diag(Severity::Error, "size must be a multiple of 4", Some(line)));
+        }
+        if size < 0 {
+            diags.push(self.diag(Severity::Error, "size cannot be negative", Some(line)));
+            return;
+        }
+
+        self.fn_section_sizes[self.cur_section.index()] += size as usize;
+
+        if self.cur_section == Section::Text {
+            if self.text_glabels.is_empty() {
+                diags.push(self.diag(Severity::Error, ".text block without an initial glabel", Some(line)));
+            }
+            self.fn_ins_inds.push((self.num_lines - 1, size as usize / 4));
+        }
+    }
+
     pub fn process_line(&mut self, line: &str, output_enc: &str) -> Result<()> {
         self.num_lines += 1;
-        
+
         // Handle line continuation
         if line.ends_with('\\') {
             self.glued_line.push_str(&line[..line.len()-1]);
             return Ok(());
         }
-        
-        let mut line = self.glued_line.clone() + line;
-        self.glued_line.clear();
 
-        let real_line = line.clone();
-        // Replace comments and strings
-        line = RE_COMMENT_OR_STRING.replace_all(&line, re_comment_replacer).to_string();
-        line = line.trim().to_string();
-        
-        // Remove label definitions
-        line = regex::Regex::new(r"^[a-zA-Z0-9_]+:\s*")
-            .map_err(|e| Error::AssemblyProcessing(e.to_string()))?
-            .replace(&line, "")
-            .to_string();
+        self.glued_line.push_str(line);
+        let real_line = std::mem::take(&mut self.glued_line);
+        let tokens = tokenize(&real_line, output_enc).map_err(|msg| self.fail(&msg, Some(&real_line)))?;
 
         let mut changed_section = false;
         let mut emitting_double = false;
 
-        if line.is_empty() {
-            // Empty line, nothing to do
-        } else if (line.starts_with("glabel ") || line.starts_with("jlabel ")) && self.cur_section == ".text" {
-            if let Some(label) = line.split_whitespace().nth(1) {
-                self.text_glabels.push(label.to_string());
+        let rest = match tokens.as_slice() {
+            [Token::Label(_), tail @ ..] => tail,
+            tail => tail,
+        };
+        let directive_name = rest.first().and_then(|t| match t {
+            Token::Directive(name) => Some(name.as_str()),
+            _ => None,
+        });
+        let operands = if rest.is_empty() { &[] } else { &rest[1..] };
+        let operand = |i: usize| operands.iter().filter_map(|t| match t {
+            Token::Operand(s) => Some(s.as_str()),
+            _ => None,
+        }).nth(i);
+        let operand_count = operands.iter().filter(|t| matches!(t, Token::Operand(_))).count();
+        let string_lit = operands.iter().find_map(|t| match t {
+            Token::StringLit { bytes_len, z } => Some((*bytes_len, *z)),
+            _ => None,
+        });
+
+        match directive_name {
+            None => {
+                // Empty line (or label-only), nothing to do
             }
-        } else if line.starts_with("glabel ") || line.starts_with("dlabel ") || 
-                 line.starts_with("jlabel ") || line.starts_with("endlabel ") || 
-                 (!line.contains(' ') && line.ends_with(':')) {
-            // Label, nothing to do
-        } else if line.starts_with(".section") || [".text", ".data", ".rdata", ".rodata", ".bss", ".late_rodata"].contains(&line.as_str()) {
-            // Section change
-            self.cur_section = if line == ".rdata" { 
-                ".rodata".to_string() 
-            } else { 
-                line.split(',')
-                    .next()
-                    .and_then(|s| s.split_whitespace().last())
-                    .ok_or_else(|| self.fail("invalid section directive", Some(&real_line)))?
-                    .to_string()
-            };
-            
-            if !vec![".data", ".text", ".rodata", ".late_rodata", ".bss"].contains(&self.cur_section.as_str()) {
-                return Err(self.fail("unrecognized .section directive", Some(&real_line)));
+            Some(name) if (name == "glabel" || name == "jlabel") && self.cur_section == Section::Text => {
+                if let Some(label) = operand(0) {
+                    self.text_glabels.push(label.to_string());
+                }
             }
-            changed_section = true;
-        } else if line.starts_with(".late_rodata_alignment") {
-            if self.cur_section != ".late_rodata" {
-                return Err(self.fail(".late_rodata_alignment must occur within .late_rodata section", Some(&real_line)));
+            Some("glabel") | Some("dlabel") | Some("jlabel") | Some("endlabel") => {
+                // Label, nothing to do
             }
-            let value = line.split_whitespace()
-                .nth(1)
-                .and_then(|s| s.parse().ok())
-                .ok_or_else(|| self.fail("invalid .late_rodata_alignment value", Some(&real_line)))?;
+            Some(name) => match name.parse::<Directive>() {
+                Ok(Directive::BareSection(section)) if operands.is_empty() => {
+                    self.cur_section = section;
+                    changed_section = true;
+                }
+                Ok(Directive::Section) => {
+                    let section = operand(0)
+                        .and_then(|s| s.split_whitespace().last())
+                        .ok_or_else(|| self.fail("invalid section directive", Some(&real_line)))?;
+                    self.cur_section = section.parse()
+                        .map_err(|()| self.fail("unrecognized .section directive", Some(&real_line)))?;
+                    changed_section = true;
+                }
+                Ok(Directive::LateRodataAlignment) => {
+                    if self.cur_section != Section::LateRodata {
+                        return Err(self.fail(".late_rodata_alignment must occur within .late_rodata section", Some(&real_line)));
+                    }
+                    let value = operand(0)
+                        .and_then(|s| s.parse().ok())
+                        .ok_or_else(|| self.fail("invalid .late_rodata_alignment value", Some(&real_line)))?;
 
-            if value != 4 && value != 8 {
-                return Err(self.fail(".late_rodata_alignment argument must be 4 or 8", Some(&real_line)));
-            }
-            if self.late_rodata_alignment != 0 && self.late_rodata_alignment != value {
-                return Err(self.fail(".late_rodata_alignment alignment assumption conflicts with earlier .double directive. Make sure to provide explicit alignment padding.", None));
-            }
-            self.late_rodata_alignment = value;
-            changed_section = true;
-        } else if line.starts_with(".incbin") {
-            let size = line.split(',')
-                .last()
-                .and_then(|s| s.trim().parse().ok())
-                .ok_or_else(|| self.fail("invalid .incbin size", Some(&real_line)))?;
-            self.add_sized(size, &real_line)?;
-        } else if line.starts_with(".word") || line.starts_with(".gpword") || line.starts_with(".float") {
-            self.align4();
-            let count = line.split(',').count();
-            self.add_sized((4 * count) as isize, &real_line)?;
-        } else if line.starts_with(".double") {
-            self.align4();
-            if self.cur_section == ".late_rodata" {
-                let align8 = self.fn_section_sizes[&self.cur_section] % 8;
-                if self.late_rodata_alignment == 0 {
-                    self.late_rodata_alignment = 8 - align8;
-                    self.late_rodata_alignment_from_content = true;
-                } else if self.late_rodata_alignment != 8 - align8 {
-                    if self.late_rodata_alignment_from_content {
-                        return Err(self.fail("found two .double directives with different start addresses mod 8. Make sure to provide explicit alignment padding.", Some(&real_line)));
-                    } else {
-                        return Err(self.fail(".double at address that is not 0 mod 8 (based on .late_rodata_alignment assumption). Make sure to provide explicit alignment padding.", Some(&real_line)));
+                    if value != 4 && value != 8 {
+                        return Err(self.fail(".late_rodata_alignment argument must be 4 or 8", Some(&real_line)));
+                    }
+                    if self.late_rodata_alignment != 0 && self.late_rodata_alignment != value {
+                        return Err(self.fail(".late_rodata_alignment alignment assumption conflicts with earlier .double directive. Make sure to provide explicit alignment padding.", None));
+                    }
+                    self.late_rodata_alignment = value;
+                    changed_section = true;
+                }
+                Ok(Directive::Incbin) => {
+                    let size = operand(operand_count.saturating_sub(1))
+                        .and_then(|s| s.trim().parse().ok())
+                        .ok_or_else(|| self.fail("invalid .incbin size", Some(&real_line)))?;
+                    if let Some(path) = operand(0).and_then(Self::unquote_path) {
+                        self.incbin_deps.push(path);
                     }
+                    self.add_sized(size, &real_line)?;
+                }
+                Ok(Directive::Include) => {
+                    // Pulls in another assembly file verbatim; record it as
+                    // a dependency but otherwise leave section sizes alone,
+                    // since its contents aren't expanded here.
+                    if let Some(path) = operand(0).and_then(Self::unquote_path) {
+                        self.incbin_deps.push(path);
+                    }
+                }
+                Ok(Directive::Word) => {
+                    self.align4();
+                    self.add_sized((4 * operand_count) as isize, &real_line)?;
+                }
+                Ok(Directive::Double) => {
+                    self.align4();
+                    if self.cur_section == Section::LateRodata {
+                        let align8 = self.fn_section_sizes[self.cur_section.index()] % 8;
+                        if self.late_rodata_alignment == 0 {
+                            self.late_rodata_alignment = 8 - align8;
+                            self.late_rodata_alignment_from_content = true;
+                        } else if self.late_rodata_alignment != 8 - align8 {
+                            if self.late_rodata_alignment_from_content {
+                                return Err(self.fail("found two .double directives with different start addresses mod 8. Make sure to provide explicit alignment padding.", Some(&real_line)));
+                            } else {
+                                return Err(self.fail(".double at address that is not 0 mod 8 (based on .late_rodata_alignment assumption). Make sure to provide explicit alignment padding.", Some(&real_line)));
+                            }
+                        }
+                    }
+                    self.add_sized((8 * operand_count) as isize, &real_line)?;
+                    emitting_double = true;
+                }
+                Ok(Directive::Space) => {
+                    let size = operand(0)
+                        .and_then(|s| s.parse().ok())
+                        .ok_or_else(|| self.fail("invalid .space size", Some(&real_line)))?;
+                    self.add_sized(size, &real_line)?;
+                }
+                Ok(Directive::Balign) => {
+                    let align = operand(0)
+                        .and_then(|s| s.parse::<usize>().ok())
+                        .ok_or_else(|| self.fail("invalid .balign value", Some(&real_line)))?;
+                    if !align.is_power_of_two() {
+                        return Err(self.fail(".balign argument must be a power of two", Some(&real_line)));
+                    }
+                    self.align_to(align);
+                }
+                Ok(Directive::Align) => {
+                    let exponent = operand(0)
+                        .and_then(|s| s.parse::<u32>().ok())
+                        .ok_or_else(|| self.fail("invalid .align value", Some(&real_line)))?;
+                    self.align_to(1usize << exponent);
+                }
+                Ok(Directive::Ascii) | Ok(Directive::Asciz) => {
+                    let (size, _z) = string_lit.expect("tokenize always produces a StringLit for an ascii directive");
+                    self.add_sized(size as isize, &real_line)?;
+                }
+                Ok(Directive::Byte) => {
+                    self.add_sized(operand_count as isize, &real_line)?;
+                }
+                Ok(Directive::Half) => {
+                    self.align2();
+                    self.add_sized(2 * operand_count as isize, &real_line)?;
+                }
+                Ok(Directive::Size) => {
+                    // Ignore .size directives
+                }
+                Ok(Directive::Fill) => {
+                    let repeat = operand(0)
+                        .and_then(|s| s.parse::<usize>().ok())
+                        .ok_or_else(|| self.fail("invalid .fill repeat count", Some(&real_line)))?;
+                    let size = match operand(1) {
+                        Some(s) => s.parse::<usize>().map_err(|_| self.fail("invalid .fill size", Some(&real_line)))?,
+                        None => 1,
+                    };
+                    self.add_sized((repeat * size) as isize, &real_line)?;
+                }
+                Ok(Directive::BareSection(_)) => {
+                    // A section name used with operands isn't a section change.
+                    return Err(self.fail("asm directive not supported", Some(&real_line)));
+                }
+                Err(()) if name.starts_with('.') => {
+                    // .macro, ...
+                    return Err(self.fail("asm directive not supported", Some(&real_line)));
+                }
+                Err(()) => {
+                    // Instruction or macro
+                    if self.cur_section != Section::Text {
+                        return Err(self.fail("instruction or macro call in non-.text section? not supported", Some(&real_line)));
+                    }
+                    self.add_sized(4, &real_line)?;
+                }
+            },
+        }
+
+        if self.cur_section == Section::LateRodata {
+            if !changed_section {
+                if emitting_double {
+                    self.late_rodata_asm_conts.push(".align 0".to_string());
+                }
+                self.late_rodata_asm_conts.push(real_line);
+                if emitting_double {
+                    self.late_rodata_asm_conts.push(".align 2".to_string());
                 }
             }
-            let count = line.split(',').count();
-            self.add_sized((8 * count) as isize, &real_line)?;
-            emitting_double = true;
-        } else if line.starts_with(".space") {
-            let size = line.split_whitespace()
-                .nth(1)
-                .and_then(|s| s.parse().ok())
-                .ok_or_else(|| self.fail("invalid .space size", Some(&real_line)))?;
-            self.add_sized(size, &real_line)?;
-        } else if line.starts_with(".balign") {
-            let align = line.split_whitespace()
-                .nth(1)
-                .and_then(|s| s.parse::<usize>().ok())
-                .ok_or_else(|| self.fail("invalid .balign value", Some(&real_line)))?;
-            if align != 4 {
-                return Err(self.fail("only .balign 4 is supported", Some(&real_line)));
+        } else {
+            self.asm_conts.push(real_line);
+        }
+
+        Ok(())
+    }
+
+    /// Non-fatal counterpart of [`Self::process_line`] for editor/LSP use:
+    /// every problem that would otherwise abort processing is instead
+    /// appended to `diags`, and parsing continues with a best-effort
+    /// fallback so that later lines still get checked.
+    pub fn process_line_collecting(&mut self, line: &str, output_enc: &str, diags: &mut Vec<Diagnostic>) {
+        self.num_lines += 1;
+
+        // Handle line continuation
+        if line.ends_with('\\') {
+            self.glued_line.push_str(&line[..line.len()-1]);
+            return;
+        }
+
+        self.glued_line.push_str(line);
+        let real_line = std::mem::take(&mut self.glued_line);
+        let mut lex_errors = Vec::new();
+        let tokens = tokenize_collecting(&real_line, output_enc, &mut lex_errors);
+        for msg in lex_errors {
+            diags.push(self.diag(Severity::Error, &msg, Some(&real_line)));
+        }
+
+        let mut changed_section = false;
+        let mut emitting_double = false;
+
+        let rest = match tokens.as_slice() {
+            [Token::Label(_), tail @ ..] => tail,
+            tail => tail,
+        };
+        let directive_name = rest.first().and_then(|t| match t {
+            Token::Directive(name) => Some(name.as_str()),
+            _ => None,
+        });
+        let operands = if rest.is_empty() { &[] } else { &rest[1..] };
+        let operand = |i: usize| operands.iter().filter_map(|t| match t {
+            Token::Operand(s) => Some(s.as_str()),
+            _ => None,
+        }).nth(i);
+        let operand_count = operands.iter().filter(|t| matches!(t, Token::Operand(_))).count();
+        let string_lit = operands.iter().find_map(|t| match t {
+            Token::StringLit { bytes_len, z } => Some((*bytes_len, *z)),
+            _ => None,
+        });
+
+        match directive_name {
+            None => {
+                // Empty line (or label-only), nothing to do
             }
-            self.align4();
-        } else if line.starts_with(".align") {
-            let align = line.split_whitespace()
-                .nth(1)
-                .and_then(|s| s.parse::<usize>().ok())
-                .ok_or_else(|| self.fail("invalid .align value", Some(&real_line)))?;
-            if align != 2 {
-                return Err(self.fail("only .align 2 is supported", Some(&real_line)));
+            Some(name) if (name == "glabel" || name == "jlabel") && self.cur_section == Section::Text => {
+                if let Some(label) = operand(0) {
+                    self.text_glabels.push(label.to_string());
+                }
             }
-            self.align4();
-        } else if line.starts_with(".asci") {
-            let z = line.starts_with(".asciz") || line.starts_with(".asciiz");
-            let size = self.count_quoted_size(&line, z, &real_line, output_enc)?;
-            self.add_sized(size as isize, &real_line)?;
-        } else if line.starts_with(".byte") {
-            self.add_sized(line.split(',').count() as isize, &real_line)?;
-        } else if line.starts_with(".half") || line.starts_with(".hword") || line.starts_with(".short") {
-            self.align2();
-            self.add_sized(2 * line.split(',').count() as isize, &real_line)?;
-        } else if line.starts_with(".size") {
-            // Ignore .size directives
-        } else if line.starts_with(".") {
-            // .macro, ...
-            return Err(self.fail("asm directive not supported", Some(&real_line)));
-        } else {
-            // Instruction or macro
-            if self.cur_section != ".text" {
-                return Err(self.fail("instruction or macro call in non-.text section? not supported", Some(&real_line)));
+            Some("glabel") | Some("dlabel") | Some("jlabel") | Some("endlabel") => {
+                // Label, nothing to do
             }
-            self.add_sized(4, &real_line)?;
+            Some(name) => match name.parse::<Directive>() {
+                Ok(Directive::BareSection(section)) if operands.is_empty() => {
+                    self.cur_section = section;
+                    changed_section = true;
+                }
+                Ok(Directive::Section) => {
+                    let parsed_section = operand(0)
+                        .and_then(|s| s.split_whitespace().last())
+                        .map(|s| s.parse::<Section>());
+                    match parsed_section {
+                        Some(Ok(section)) => {
+                            self.cur_section = section;
+                            changed_section = true;
+                        }
+                        Some(Err(())) => diags.push(self.diag(Severity::Error, "unrecognized .section directive", Some(&real_line))),
+                        None => diags.push(self.diag(Severity::Error, "invalid section directive", Some(&real_line))),
+                    }
+                }
+                Ok(Directive::LateRodataAlignment) => {
+                    if self.cur_section != Section::LateRodata {
+                        diags.push(self.diag(Severity::Error, ".late_rodata_alignment must occur within .late_rodata section", Some(&real_line)));
+                    } else {
+                        let value: Option<usize> = operand(0).and_then(|s| s.parse().ok());
+                        match value {
+                            Some(value) if value == 4 || value == 8 => {
+                                if self.late_rodata_alignment != 0 && self.late_rodata_alignment != value {
+                                    diags.push(self.diag(Severity::Error, ".late_rodata_alignment alignment assumption conflicts with earlier .double directive. Make sure to provide explicit alignment padding.", None));
+                                }
+                                self.late_rodata_alignment = value;
+                                changed_section = true;
+                            }
+                            Some(_) => diags.push(self.diag(Severity::Error, ".late_rodata_alignment argument must be 4 or 8", Some(&real_line))),
+                            None => diags.push(self.diag(Severity::Error, "invalid .late_rodata_alignment value", Some(&real_line))),
+                        }
+                    }
+                }
+                Ok(Directive::Incbin) => {
+                    if let Some(path) = operand(0).and_then(Self::unquote_path) {
+                        self.incbin_deps.push(path);
+                    }
+                    match operand(operand_count.saturating_sub(1)).and_then(|s| s.trim().parse().ok()) {
+                        Some(size) => self.add_sized_collecting(size, &real_line, diags),
+                        None => diags.push(self.diag(Severity::Error, "invalid .incbin size", Some(&real_line))),
+                    }
+                }
+                Ok(Directive::Include) => {
+                    if let Some(path) = operand(0).and_then(Self::unquote_path) {
+                        self.incbin_deps.push(path);
+                    }
+                }
+                Ok(Directive::Word) => {
+                    self.align4();
+                    self.add_sized_collecting((4 * operand_count) as isize, &real_line, diags);
+                }
+                Ok(Directive::Double) => {
+                    self.align4();
+                    if self.cur_section == Section::LateRodata {
+                        let align8 = self.fn_section_sizes[self.cur_section.index()] % 8;
+                        if self.late_rodata_alignment == 0 {
+                            self.late_rodata_alignment = 8 - align8;
+                            self.late_rodata_alignment_from_content = true;
+                        } else if self.late_rodata_alignment != 8 - align8 {
+                            if self.late_rodata_alignment_from_content {
+                                diags.push(self.diag(Severity::Error, "found two .double directives with different start addresses mod 8. Make sure to provide explicit alignment padding.", Some(&real_line)));
+                            } else {
+                                diags.push(self.diag(Severity::Error, ".double at address that is not 0 mod 8 (based on .late_rodata_alignment assumption). Make sure to provide explicit alignment padding.", Some(&real_line)));
+                            }
+                        }
+                    }
+                    self.add_sized_collecting((8 * operand_count) as isize, &real_line, diags);
+                    emitting_double = true;
+                }
+                Ok(Directive::Space) => {
+                    match operand(0).and_then(|s| s.parse().ok()) {
+                        Some(size) => self.add_sized_collecting(size, &real_line, diags),
+                        None => diags.push(self.diag(Severity::Error, "invalid .space size", Some(&real_line))),
+                    }
+                }
+                Ok(Directive::Balign) => {
+                    match operand(0).and_then(|s| s.parse::<usize>().ok()) {
+                        Some(align) if align.is_power_of_two() => self.align_to(align),
+                        Some(_) => diags.push(self.diag(Severity::Error, ".balign argument must be a power of two", Some(&real_line))),
+                        None => diags.push(self.diag(Severity::Error, "invalid .balign value", Some(&real_line))),
+                    }
+                }
+                Ok(Directive::Align) => {
+                    match operand(0).and_then(|s| s.parse::<u32>().ok()) {
+                        Some(exponent) => self.align_to(1usize << exponent),
+                        None => diags.push(self.diag(Severity::Error, "invalid .align value", Some(&real_line))),
+                    }
+                }
+                Ok(Directive::Ascii) | Ok(Directive::Asciz) => {
+                    let (size, _z) = string_lit.unwrap_or((0, false));
+                    self.add_sized_collecting(size as isize, &real_line, diags);
+                }
+                Ok(Directive::Byte) => {
+                    self.add_sized_collecting(operand_count as isize, &real_line, diags);
+                }
+                Ok(Directive::Half) => {
+                    self.align2();
+                    self.add_sized_collecting(2 * operand_count as isize, &real_line, diags);
+                }
+                Ok(Directive::Size) => {
+                    // Ignore .size directives
+                }
+                Ok(Directive::Fill) => {
+                    let repeat = operand(0).and_then(|s| s.parse::<usize>().ok());
+                    let size = match operand(1) {
+                        Some(s) => s.parse::<usize>().ok(),
+                        None => Some(1),
+                    };
+                    match (repeat, size) {
+                        (Some(repeat), Some(size)) => self.add_sized_collecting((repeat * size) as isize, &real_line, diags),
+                        _ => diags.push(self.diag(Severity::Error, "invalid .fill arguments", Some(&real_line))),
+                    }
+                }
+                Ok(Directive::BareSection(_)) => {
+                    // A section name used with operands isn't a section change.
+                    diags.push(self.diag(Severity::Error, "asm directive not supported", Some(&real_line)));
+                }
+                Err(()) if name.starts_with('.') => {
+                    // .macro, ...
+                    diags.push(self.diag(Severity::Error, "asm directive not supported", Some(&real_line)));
+                }
+                Err(()) => {
+                    // Instruction or macro
+                    if self.cur_section != Section::Text {
+                        diags.push(self.diag(Severity::Error, "instruction or macro call in non-.text section? not supported", Some(&real_line)));
+                    } else {
+                        self.add_sized_collecting(4, &real_line, diags);
+                    }
+                }
+            },
         }
 
-        if self.cur_section == ".late_rodata" {
+        if self.cur_section == Section::LateRodata {
             if !changed_section {
                 if emitting_double {
                     self.late_rodata_asm_conts.push(".align 0".to_string());
@@ -330,12 +667,10 @@ impl GlobalAsmBlock {
         } else {
             self.asm_conts.push(real_line);
         }
-
-        Ok(())
     }
 
     pub fn finish(mut self, state: &mut GlobalState) -> Result<(Vec<String>, Function)> {
-        if self.cur_section == ".text" && self.text_glabels.is_empty() {
+        if self.cur_section == Section::Text && self.text_glabels.is_empty() {
             return Err(self.fail("no function labels found", None));
         }
 
@@ -346,8 +681,8 @@ impl GlobalAsmBlock {
         let mut text_name = None;
 
         // Handle text section and late rodata
-        if self.fn_section_sizes[".text"] > 0 || !self.late_rodata_asm_conts.is_empty() {
-            let instr_count = self.fn_section_sizes[".text"] / 4;
+        if self.fn_section_sizes[Section::Text.index()] > 0 || !self.late_rodata_asm_conts.is_empty() {
+            let instr_count = self.fn_section_sizes[Section::Text.index()] / 4;
             let mut tot_emitted = 0;
             let mut tot_skipped = 0;
             let mut fn_emitted = 0;
@@ -393,8 +728,8 @@ impl GlobalAsmBlock {
         }
 
         let mut late_rodata_fn_output = Vec::new();
-        if self.fn_section_sizes[".late_rodata"] > 0 {
-            let size = self.fn_section_sizes[".late_rodata"] / 4;
+        if self.fn_section_sizes[Section::LateRodata.index()] > 0 {
+            let size = self.fn_section_sizes[Section::LateRodata.index()] / 4;
             let mut skip_next = false;
             let mut needs_double = self.late_rodata_alignment != 0;
             let mut extra_mips1_nop = false;
@@ -483,40 +818,40 @@ impl GlobalAsmBlock {
         // Handle section-specific names and declarations
         let mut output = Vec::new();
         
-        if self.fn_section_sizes[".rodata"] > 0 {
+        if self.fn_section_sizes[Section::Rodata.index()] > 0 {
             if state.pascal {
                 return Err(self.fail(".rodata isn't supported with Pascal for now", None));
             }
             let rodata_name = format!("_asmpp_rodata{}", state.get_next_id());
             output.push(format!(" const char {}[{}] = {{1}};", 
-                rodata_name, self.fn_section_sizes[".rodata"]));
+                rodata_name, self.fn_section_sizes[Section::Rodata.index()]));
             data.insert(".rodata".to_string(), 
-                (rodata_name, self.fn_section_sizes[".rodata"]));
+                (rodata_name, self.fn_section_sizes[Section::Rodata.index()]));
         }
 
-        if self.fn_section_sizes[".data"] > 0 {
+        if self.fn_section_sizes[Section::Data.index()] > 0 {
             let data_name = format!("_asmpp_data{}", state.get_next_id());
             let line = if state.pascal {
                 format!(" var {}: packed array[1..{}] of char := [otherwise: 0];",
-                    data_name, self.fn_section_sizes[".data"])
+                    data_name, self.fn_section_sizes[Section::Data.index()])
             } else {
                 format!(" char {}[{}] = {{1}};",
-                    data_name, self.fn_section_sizes[".data"])
+                    data_name, self.fn_section_sizes[Section::Data.index()])
             };
             output.push(line);
             data.insert(".data".to_string(), 
-                (data_name, self.fn_section_sizes[".data"]));
+                (data_name, self.fn_section_sizes[Section::Data.index()]));
         }
 
-        if self.fn_section_sizes[".bss"] > 0 {
+        if self.fn_section_sizes[Section::Bss.index()] > 0 {
             if state.pascal {
                 return Err(self.fail(".bss isn't supported with Pascal", None));
             }
             let bss_name = format!("_asmpp_bss{}", state.get_next_id());
             output.push(format!(" char {}[{}];",
-                bss_name, self.fn_section_sizes[".bss"]));
+                bss_name, self.fn_section_sizes[Section::Bss.index()]));
             data.insert(".bss".to_string(),
-                (bss_name, self.fn_section_sizes[".bss"]));
+                (bss_name, self.fn_section_sizes[Section::Bss.index()]));
         }
 
         Ok((output, Function {
@@ -530,4 +865,207 @@ impl GlobalAsmBlock {
             late_rodata: None,
         }))
     }
+
+    /// Non-fatal counterpart of [`Self::finish`]: late-rodata-ratio
+    /// violations and missing glabels are recorded as diagnostics rather
+    /// than aborting, so a best-effort `Function` is always produced.
+    pub fn finish_collecting(mut self, state: &mut GlobalState, diags: &mut Vec<Diagnostic>) -> (Vec<String>, Function) {
+        if self.cur_section == Section::Text && self.text_glabels.is_empty() {
+            diags.push(self.diag(Severity::Error, "no function labels found", None));
+        }
+
+        let mut late_rodata_dummy_bytes = Vec::new();
+        let mut late_rodata_asm_conts = Vec::new();
+        let mut jtbl_rodata_size = 0;
+        let mut data = HashMap::new();
+
+        let instr_count = self.fn_section_sizes[Section::Text.index()] / 4;
+
+        // Handle text section and late rodata
+        if self.fn_section_sizes[Section::Text.index()] > 0 || !self.late_rodata_asm_conts.is_empty() {
+            let mut tot_emitted = 0;
+            let mut tot_skipped = 0;
+            let mut fn_emitted = 0;
+            let mut fn_skipped = 0;
+            let mut skipping = true;
+
+            for (_line_no, count) in &self.fn_ins_inds {
+                for _ in 0..*count {
+                    if fn_emitted > MAX_FN_SIZE &&
+                       instr_count - tot_emitted > state.min_instr_count {
+                        // Reset counters when function gets too large
+                        fn_emitted = 0;
+                        fn_skipped = 0;
+                        skipping = true;
+                    }
+
+                    if skipping && fn_skipped < state.skip_instr_count {
+                        fn_skipped += 1;
+                        tot_skipped += 1;
+                    } else {
+                        skipping = false;
+                        tot_emitted += 1;
+                        fn_emitted += 1;
+                    }
+                }
+            }
+
+            // Check late rodata ratio
+            if !self.late_rodata_asm_conts.is_empty() {
+                let size = self.late_rodata_asm_conts.len() / 3;
+                let available = instr_count - tot_skipped;
+                if size * 3 > available {
+                    diags.push(self.diag(
+                        Severity::Error,
+                        &format!(
+                            "late rodata to text ratio is too high: {} / {} must be <= 1/3\n\
+                             add .late_rodata_alignment (4|8) to the .late_rodata block to double the allowed ratio.",
+                            size, available
+                        ),
+                        None,
+                    ));
+                }
+            }
+        }
+
+        let mut late_rodata_fn_output = Vec::new();
+        if self.fn_section_sizes[Section::LateRodata.index()] > 0 {
+            let size = self.fn_section_sizes[Section::LateRodata.index()] / 4;
+            let mut skip_next = false;
+            let mut needs_double = self.late_rodata_alignment != 0;
+            let mut extra_mips1_nop = false;
+
+            // Pascal vs C-specific sizes
+            let (jtbl_size, jtbl_min_rodata_size) = if state.pascal {
+                (if state.mips1 { 9 } else { 8 }, 2)
+            } else {
+                (if state.mips1 { 11 } else { 9 }, 5)
+            };
+
+            for i in 0..size {
+                if skip_next {
+                    skip_next = false;
+                    continue;
+                }
+
+                if !needs_double && state.use_jtbl_for_rodata && i >= 1
+                   && size - i >= jtbl_min_rodata_size
+                   && instr_count - late_rodata_fn_output.len() >= jtbl_size + 1 {
+                    // Generate jump table
+                    let line = if state.pascal {
+                        let cases = (0..size-i)
+                            .map(|case| format!("{}: ;", case))
+                            .collect::<Vec<_>>()
+                            .join(" ");
+                        format!("case 0 of {} otherwise end;", cases)
+                    } else {
+                        let cases = (0..size-i)
+                            .map(|case| format!("case {}:", case))
+                            .collect::<Vec<_>>()
+                            .join(" ");
+                        format!("switch (*(volatile int*)0) {{ {} ; }}", cases)
+                    };
+                    late_rodata_fn_output.push(line);
+                    late_rodata_fn_output.extend(vec![String::new(); jtbl_size - 1]);
+                    jtbl_rodata_size = (size - i) * 4;
+                    extra_mips1_nop = i != 2;
+                    break;
+                }
+
+                // Handle doubles and floats with MIPS1 considerations
+                let dummy_bytes = state.next_late_rodata_hex();
+                late_rodata_dummy_bytes.push(dummy_bytes.clone());
+
+                if self.late_rodata_alignment == 4 * ((i + 1) % 2 + 1) && i + 1 < size {
+                    // Double handling
+                    let dummy_bytes2 = state.next_late_rodata_hex();
+                    late_rodata_dummy_bytes.push(dummy_bytes2.clone());
+                    let combined = [dummy_bytes, dummy_bytes2].concat();
+                    let fval = f64::from_be_bytes(combined.try_into().unwrap());
+
+                    let line = if state.pascal {
+                        state.pascal_assignment("d", &fval.to_string())
+                    } else {
+                        format!("*(volatile double*)0 = {};", fval)
+                    };
+                    late_rodata_fn_output.push(line);
+                    skip_next = true;
+                    needs_double = false;
+
+                    if state.mips1 {
+                        // MIPS1 doesn't have ldc1/sdc1
+                        late_rodata_fn_output.extend(vec![String::new(); 2]);
+                    }
+                    extra_mips1_nop = false;
+                } else {
+                    // Float handling
+                    let fval = f32::from_be_bytes(dummy_bytes.try_into().unwrap());
+                    let line = if state.pascal {
+                        state.pascal_assignment("f", &fval.to_string())
+                    } else {
+                        format!("*(volatile float*)0 = {}f;", fval)
+                    };
+                    late_rodata_fn_output.push(line);
+                    extra_mips1_nop = true;
+                }
+                late_rodata_fn_output.extend(vec![String::new(); 2]);
+            }
+
+            if state.mips1 && extra_mips1_nop {
+                late_rodata_fn_output.push(String::new());
+            }
+        }
+
+        // Handle section-specific names and declarations
+        let mut output = Vec::new();
+
+        if self.fn_section_sizes[Section::Rodata.index()] > 0 {
+            if state.pascal {
+                diags.push(self.diag(Severity::Error, ".rodata isn't supported with Pascal for now", None));
+            } else {
+                let rodata_name = state.make_name("rodata");
+                output.push(format!(" const char {}[{}] = {{1}};",
+                    rodata_name, self.fn_section_sizes[Section::Rodata.index()]));
+                data.insert(".rodata".to_string(),
+                    (rodata_name, self.fn_section_sizes[Section::Rodata.index()]));
+            }
+        }
+
+        if self.fn_section_sizes[Section::Data.index()] > 0 {
+            let data_name = state.make_name("data");
+            let line = if state.pascal {
+                format!(" var {}: packed array[1..{}] of char := [otherwise: 0];",
+                    data_name, self.fn_section_sizes[Section::Data.index()])
+            } else {
+                format!(" char {}[{}] = {{1}};",
+                    data_name, self.fn_section_sizes[Section::Data.index()])
+            };
+            output.push(line);
+            data.insert(".data".to_string(),
+                (data_name, self.fn_section_sizes[Section::Data.index()]));
+        }
+
+        if self.fn_section_sizes[Section::Bss.index()] > 0 {
+            if state.pascal {
+                diags.push(self.diag(Severity::Error, ".bss isn't supported with Pascal", None));
+            } else {
+                let bss_name = state.make_name("bss");
+                output.push(format!(" char {}[{}];",
+                    bss_name, self.fn_section_sizes[Section::Bss.index()]));
+                data.insert(".bss".to_string(),
+                    (bss_name, self.fn_section_sizes[Section::Bss.index()]));
+            }
+        }
+
+        (output, Function {
+            text_glabels: self.text_glabels,
+            asm_conts: self.asm_conts,
+            late_rodata_dummy_bytes,
+            jtbl_rodata_size,
+            late_rodata_asm_conts,
+            fn_desc: self.fn_desc,
+            data,
+            late_rodata: None,
+        })
+    }
 }
@@ -0,0 +1,276 @@
+use encoding_rs::Encoding;
+
+/// A token produced by lexing one logical (post-line-continuation) assembly
+/// source line. [`crate::asm::block::GlobalAsmBlock::process_line`] and
+/// `process_line_collecting` drive their directive dispatch off these
+/// instead of re-scanning the raw text with a freshly compiled regex on
+/// every call.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Label(String),
+    Directive(String),
+    Operand(String),
+    StringLit { bytes_len: usize, z: bool },
+}
+
+/// Lex `line` (comments and all) into a token stream: an optional leading
+/// `name:` label, then a directive/mnemonic name and its operands.
+/// `#...` line comments and `/* ... */` block comments are stripped in the
+/// same single pass that tracks quote state, so neither is ever confused
+/// for one that happens to appear inside a string literal. The operand of
+/// an `.ascii`/`.asciz`/`.asciiz` directive is lexed directly into a
+/// [`Token::StringLit`] with its encoded byte length already computed
+/// (honoring `\x..`/octal `\nnn` escapes and `"a""b"` glued-string
+/// adjacency), so callers no longer need to re-scan it themselves.
+pub fn tokenize(line: &str, output_enc: &str) -> Result<Vec<Token>, String> {
+    let clean = strip_comments(line);
+    let trimmed = clean.trim();
+    let (label, rest) = split_label(trimmed);
+
+    let rest = rest.trim();
+    let mut tokens = Vec::new();
+    if let Some(label) = label {
+        tokens.push(Token::Label(label));
+    }
+    if rest.is_empty() {
+        return Ok(tokens);
+    }
+
+    let split_at = rest.find(char::is_whitespace).unwrap_or(rest.len());
+    let directive = &rest[..split_at];
+    let operands = rest[split_at..].trim_start();
+    tokens.push(Token::Directive(directive.to_string()));
+
+    if directive == ".ascii" || directive == ".asciz" || directive == ".asciiz" {
+        let z = directive == ".asciz" || directive == ".asciiz";
+        tokens.push(lex_string_lit(operands, z, output_enc)?);
+    } else {
+        for operand in split_top_level_commas(operands) {
+            let operand = operand.trim();
+            if !operand.is_empty() {
+                tokens.push(Token::Operand(operand.to_string()));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Non-fatal counterpart of [`tokenize`] for editor/LSP use: instead of
+/// aborting on a malformed string literal, a best-effort `StringLit` is
+/// produced and the problem is appended to `errors` as a message (without
+/// location context - the caller attaches that).
+pub fn tokenize_collecting(line: &str, output_enc: &str, errors: &mut Vec<String>) -> Vec<Token> {
+    let clean = strip_comments(line);
+    let trimmed = clean.trim();
+    let (label, rest) = split_label(trimmed);
+
+    let rest = rest.trim();
+    let mut tokens = Vec::new();
+    if let Some(label) = label {
+        tokens.push(Token::Label(label));
+    }
+    if rest.is_empty() {
+        return tokens;
+    }
+
+    let split_at = rest.find(char::is_whitespace).unwrap_or(rest.len());
+    let directive = &rest[..split_at];
+    let operands = rest[split_at..].trim_start();
+    tokens.push(Token::Directive(directive.to_string()));
+
+    if directive == ".ascii" || directive == ".asciz" || directive == ".asciiz" {
+        let z = directive == ".asciz" || directive == ".asciiz";
+        tokens.push(lex_string_lit_collecting(operands, z, output_enc, errors));
+    } else {
+        for operand in split_top_level_commas(operands) {
+            let operand = operand.trim();
+            if !operand.is_empty() {
+                tokens.push(Token::Operand(operand.to_string()));
+            }
+        }
+    }
+
+    tokens
+}
+
+/// Remove `#...` line comments and `/* ... */` block comments, leaving
+/// quoted string literals untouched so a `#` or `/*` inside one isn't
+/// mistaken for a comment.
+fn strip_comments(line: &str) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut out = String::with_capacity(chars.len());
+    let mut i = 0;
+    let mut in_quote = false;
+    while i < chars.len() {
+        let c = chars[i];
+        if in_quote {
+            out.push(c);
+            if c == '\\' && i + 1 < chars.len() {
+                out.push(chars[i + 1]);
+                i += 2;
+                continue;
+            }
+            if c == '"' {
+                in_quote = false;
+            }
+            i += 1;
+            continue;
+        }
+        match c {
+            '"' => {
+                in_quote = true;
+                out.push(c);
+                i += 1;
+            }
+            '#' => break,
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    i += 1;
+                }
+                i = (i + 2).min(chars.len());
+                out.push(' ');
+            }
+            _ => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Split off a leading `identifier:` label, if present.
+fn split_label(line: &str) -> (Option<String>, &str) {
+    let bytes = line.as_bytes();
+    let mut end = 0;
+    while end < bytes.len() && (bytes[end].is_ascii_alphanumeric() || bytes[end] == b'_') {
+        end += 1;
+    }
+    if end > 0 && bytes.get(end) == Some(&b':') {
+        (Some(line[..end].to_string()), line[end + 1..].trim_start())
+    } else {
+        (None, line)
+    }
+}
+
+/// Split `text` on commas that aren't inside a quoted string literal.
+fn split_top_level_commas(text: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut cur = String::new();
+    let mut in_quote = false;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if in_quote {
+            cur.push(c);
+            if c == '\\' {
+                if let Some(next) = chars.next() {
+                    cur.push(next);
+                }
+                continue;
+            }
+            if c == '"' {
+                in_quote = false;
+            }
+            continue;
+        }
+        match c {
+            '"' => {
+                in_quote = true;
+                cur.push(c);
+            }
+            ',' => parts.push(std::mem::take(&mut cur)),
+            _ => cur.push(c),
+        }
+    }
+    parts.push(cur);
+    parts
+}
+
+/// Lex the operand of an `.ascii`/`.asciz`/`.asciiz` directive into a
+/// `StringLit`, decoding escapes and encoding the content as `output_enc`
+/// bytes.
+fn lex_string_lit(operands: &str, z: bool, output_enc: &str) -> Result<Token, String> {
+    let mut errors = Vec::new();
+    let token = lex_string_lit_collecting(operands, z, output_enc, &mut errors);
+    match errors.into_iter().next() {
+        Some(msg) => Err(msg),
+        None => Ok(token),
+    }
+}
+
+/// Non-fatal counterpart of [`lex_string_lit`]: every problem found is
+/// appended to `errors` instead of aborting, and a best-effort `StringLit`
+/// is always returned.
+fn lex_string_lit_collecting(operands: &str, z: bool, output_enc: &str, errors: &mut Vec<String>) -> Token {
+    let enc = match Encoding::for_label(output_enc) {
+        Some(enc) => enc,
+        None => {
+            errors.push("Invalid encoding".to_string());
+            return Token::StringLit { bytes_len: 0, z };
+        }
+    };
+    let (encoded, _, _) = enc.encode(operands);
+    let line = String::from_utf8_lossy(&encoded);
+
+    let mut in_quote = false;
+    let mut has_comma = true;
+    let mut num_parts = 0;
+    let mut ret = 0;
+    let mut i = 0;
+    let chars: Vec<char> = line.chars().collect();
+    let digits = "0123456789";
+
+    while i < chars.len() {
+        let c = chars[i];
+        i += 1;
+        if !in_quote {
+            if c == '"' {
+                in_quote = true;
+                if z && !has_comma {
+                    errors.push(".asciiz with glued strings is not supported due to GNU as version diffs".to_string());
+                }
+                num_parts += 1;
+            } else if c == ',' {
+                has_comma = true;
+            }
+        } else {
+            if c == '"' {
+                in_quote = false;
+                has_comma = false;
+                continue;
+            }
+            ret += 1;
+            if c != '\\' {
+                continue;
+            }
+            if i == chars.len() {
+                errors.push("backslash at end of line not supported".to_string());
+                break;
+            }
+            let c = chars[i];
+            i += 1;
+            if c == 'x' {
+                while i < chars.len() && (digits.contains(chars[i]) || "abcdefABCDEF".contains(chars[i])) {
+                    i += 1;
+                }
+            } else if digits.contains(c) {
+                let mut it = 0;
+                while i < chars.len() && digits.contains(chars[i]) && it < 2 {
+                    i += 1;
+                    it += 1;
+                }
+            }
+        }
+    }
+
+    if in_quote {
+        errors.push("unterminated string literal".to_string());
+    }
+    if num_parts == 0 {
+        errors.push(".ascii with no string".to_string());
+    }
+
+    Token::StringLit { bytes_len: if z { ret + num_parts } else { ret }, z }
+}
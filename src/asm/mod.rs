@@ -1,6 +1,8 @@
 pub mod block;
 pub mod function;
+pub mod lexer;
 
 // Re-export commonly used types
-pub use block::GlobalAsmBlock;
+pub use block::{GlobalAsmBlock, Diagnostic, Severity, Section};
 pub use function::Function;
+pub use lexer::Token;
@@ -61,6 +61,18 @@ pub struct Args {
     #[arg(long)]
     pub force: bool,
 
+    /// Write a GCC `-MMD`-style Makefile dependency file listing every
+    /// source file referenced (via `#include`/`GLOBAL_ASM`) while parsing,
+    /// so a build system can rebuild the output when they change
+    #[arg(long, value_name = "PATH")]
+    pub write_deps: Option<PathBuf>,
+
+    /// Override the rule target written to `--write-deps` (defaults to
+    /// `--post-process`'s path, or `<FILE>` with its extension replaced by
+    /// `.o` when only pre-processing)
+    #[arg(long, value_name = "NAME")]
+    pub deps_target: Option<String>,
+
     /// Replace floats with their encoded hexadecimal representation in CutsceneData data
     #[arg(long)]
     pub encode_cutscene_data_floats: bool,
@@ -148,7 +160,14 @@ pub fn run<W: std::io::Write>(
         let file = File::open(&args.filename)?;
         let mut reader = BufReader::new(file);
         let functions = parse_source(&mut reader, &opts, &mut deps, outfile)?;
-        
+
+        if let Some(depfile) = &args.write_deps {
+            let target = args.deps_target.clone().unwrap_or_else(|| {
+                args.filename.with_extension("o").to_string_lossy().into_owned()
+            });
+            write_depfile(depfile, &target, &deps)?;
+        }
+
         return Ok(Some(ProcessorOutput {
             functions,
             dependencies: deps,
@@ -194,5 +213,29 @@ pub fn run<W: std::io::Write>(
         &args.convert_statics,
     )?;
 
+    if let Some(depfile) = &args.write_deps {
+        let target = args.deps_target.clone().unwrap_or_else(|| objfile.to_string_lossy().into_owned());
+        write_depfile(depfile, &target, &deps)?;
+    }
+
     Ok(None)
 }
+
+/// Write a GCC `-MMD`-style dependency file: a single rule with every
+/// dependency on its own continuation line (`target: \`, `  dep1 \`,
+/// `  dep2`), escaping spaces in each path as `\ `.
+fn write_depfile(path: &Path, target: &str, deps: &[String]) -> Result<()> {
+    let mut out = escape_dep_path(target);
+    out.push(':');
+    for dep in deps {
+        out.push_str(" \\\n  ");
+        out.push_str(&escape_dep_path(dep));
+    }
+    out.push('\n');
+    std::fs::write(path, out)?;
+    Ok(())
+}
+
+fn escape_dep_path(path: &str) -> String {
+    path.replace(' ', "\\ ")
+}
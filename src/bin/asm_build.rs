@@ -4,6 +4,8 @@ use std::fs::{self, File};
 use std::io::{self, BufWriter, Write};
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
 use tempfile::Builder;
 use uuid::Uuid;
 
@@ -19,11 +21,26 @@ struct BuildConfig {
     out_file: PathBuf,
     in_file: PathBuf,
     keep_preprocessed: bool,
+    timeout: Option<Duration>,
+    time_passes: bool,
 }
 
 fn parse_args() -> BuildConfig {
-    let args: Vec<String> = env::args().skip(1).collect();
-    
+    let mut args: Vec<String> = env::args().skip(1).collect();
+
+    // --timeout is a build.rs-level option, not an asmproc/compiler/assembler
+    // flag, so pull it (and its value) out before splitting the rest on the
+    // "--" separators below.
+    let timeout = args.iter().position(|arg| arg == "--timeout").map(|idx| {
+        let value = args
+            .get(idx + 1)
+            .expect("--timeout requires a value (seconds)")
+            .parse::<u64>()
+            .expect("--timeout value must be an integer number of seconds");
+        args.drain(idx..idx + 2);
+        Duration::from_secs(value)
+    });
+
     // Find separators
     let sep1 = args.iter()
         .position(|arg| arg == "--")
@@ -80,7 +97,26 @@ fn parse_args() -> BuildConfig {
         out_file,
         in_file,
         keep_preprocessed: false,
+        timeout,
+        time_passes: args[..sep0].iter().any(|arg| arg == "--time-passes"),
+    }
+}
+
+/// Print a compact `phase | ms | % of total` table to stderr, for profiling
+/// whether a slow build is dominated by asm-processor's own parsing, the
+/// assembler, or the compiler (e.g. `qemu-irix`).
+fn print_pass_timings(timings: &[(&str, Duration)]) {
+    let total = timings.iter().map(|(_, d)| *d).sum::<Duration>();
+    eprintln!("{:<20} {:>10} {:>8}", "phase", "ms", "%");
+    for (name, duration) in timings {
+        let pct = if total.as_secs_f64() > 0.0 {
+            duration.as_secs_f64() / total.as_secs_f64() * 100.0
+        } else {
+            0.0
+        };
+        eprintln!("{:<20} {:>10.1} {:>7.1}%", name, duration.as_secs_f64() * 1000.0, pct);
     }
+    eprintln!("{:<20} {:>10.1} {:>7.1}%", "total", total.as_secs_f64() * 1000.0, 100.0);
 }
 
 fn run_compiler(
@@ -111,7 +147,36 @@ fn run_compiler(
 
     println!("Compiler command: {:?}", compile_command);
 
-    let status = compile_command.status()?;
+    // `qemu-irix` (or a hung assembler) can wedge forever on malformed input,
+    // so rather than blocking on `status()` we spawn the child and poll
+    // `try_wait()` until it exits or the deadline passes, killing it on timeout.
+    let mut child = compile_command.spawn()?;
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+
+        if let Some(timeout) = config.timeout {
+            if start.elapsed() >= timeout {
+                child.kill()?;
+                child.wait()?;
+                return Err(format!(
+                    "Compiler timed out after {:?} on file {}. Command line:\n{}",
+                    start.elapsed(),
+                    config.in_file.display(),
+                    compile_command
+                        .get_args()
+                        .map(|arg| arg.to_string_lossy())
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                ).into());
+            }
+        }
+
+        thread::sleep(Duration::from_millis(100));
+    };
+
     if !status.success() {
         return Err(format!(
             "Failed to compile file {}. Command line:\n{}",
@@ -183,9 +248,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         opt_g: config.asmproc_flags.contains(&"-g".to_string()),
     };
 
-    if let Some(ProcessorOutput { functions, dependencies }) = run(&args, Some(&mut writer))? {
+    let mut timings: Vec<(&str, Duration)> = Vec::new();
+
+    let preprocess_start = Instant::now();
+    let preprocess_result = run(&args, Some(&mut writer))?;
+    timings.push(("preprocess", preprocess_start.elapsed()));
+
+    if let Some(ProcessorOutput { functions, dependencies }) = preprocess_result {
         // Run compiler
+        let compile_start = Instant::now();
         run_compiler(&config, &preprocessed_path)?;
+        timings.push(("compile", compile_start.elapsed()));
 
         // Post-process
         let post_args = Args {
@@ -209,10 +282,16 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             opt_g: config.asmproc_flags.contains(&"-g".to_string()),
         };
 
+        let postprocess_start = Instant::now();
         run::<std::io::BufWriter<File>>(&post_args, None)?;
-        
+        timings.push(("postprocess", postprocess_start.elapsed()));
+
         write_deps_file(&config.out_file, Some(dependencies))?;
     }
 
+    if config.time_passes {
+        print_pass_timings(&timings);
+    }
+
     Ok(())
 }
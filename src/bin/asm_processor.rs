@@ -9,7 +9,9 @@ use asm_processor::{
     Function,
     parse_source,
     utils::options::Opts,
-    objfile::fixup_objfile,
+    utils::cli::merge_env_opts,
+    objfile::{fixup_archive, fixup_objfile, parse_linker_map, parse_symbol_overrides},
+    arch,
 };
 
 #[derive(Parser)]
@@ -24,14 +26,23 @@ struct Args {
     #[arg(value_name = "FILE")]
     filename: PathBuf,
 
-    /// Path to .o file to post-process
+    /// Path to .o file to post-process (or a .a archive, whose ELF members
+    /// are each post-processed in place)
     #[arg(long)]
     post_process: Option<PathBuf>,
 
-    /// Assembler command (e.g. "mips-linux-gnu-as -march=vr4300 -mabi=32")
+    /// Assembler command (e.g. "mips-linux-gnu-as -march=vr4300 -mabi=32");
+    /// defaults to the selected `--arch`'s own assembler invocation
     #[arg(long)]
     assembler: Option<String>,
 
+    /// Target architecture profile, selecting instruction-count heuristics,
+    /// jump-table placement, the default assembler, and recognized
+    /// relocation types
+    #[arg(long, default_value = "mips")]
+    #[arg(value_parser = ["mips", "ppc"])]
+    arch: String,
+
     /// Path to a file containing a prelude to the assembly file
     #[arg(long)]
     asm_prelude: Option<PathBuf>,
@@ -48,11 +59,46 @@ struct Args {
     #[arg(long)]
     drop_mdebug_gptab: bool,
 
-    /// Change static symbol visibility
+    /// Drop the `.comment` section entirely instead of merging the
+    /// assembled temp object's producer string into it, for builds that
+    /// want byte-identical output without compiler version strings
+    #[arg(long)]
+    drop_comment: bool,
+
+    /// Change static symbol visibility. `from-map` looks each recovered
+    /// static up in `--symbol-map` instead, falling back to `local` unless
+    /// something outside its translation unit already references it
     #[arg(long, value_name = "MODE", default_value = "local")]
-    #[arg(value_parser = ["no", "local", "global", "global-with-filename"])]
+    #[arg(value_parser = ["no", "local", "global", "global-with-filename", "from-map"])]
     convert_statics: String,
 
+    /// Path to a symbol control file overriding visibility/size/liveness
+    /// for individual static symbols (see `objfile::parse_symbol_overrides`)
+    #[arg(long)]
+    symbols: Option<PathBuf>,
+
+    /// Path to a linker map (GNU ld `--cref` or Metrowerks CodeWarrior
+    /// style) to resolve real bindings from for `--convert-statics
+    /// from-map` (see `objfile::parse_linker_map`)
+    #[arg(long)]
+    symbol_map: Option<PathBuf>,
+
+    /// Emit a `.note.split` section recording the name, section and size
+    /// of each injected function, so downstream diff tools can still match
+    /// them up after the merge shifts addresses and symbol indices around
+    #[arg(long)]
+    split_meta: bool,
+
+    /// ELF parsing backend for `--post-process`. `builtin` is the
+    /// hand-rolled zero-dependency parser; `object` (only available when
+    /// built with the `backend-object` feature) validates the file through
+    /// the `object` crate before post-processing, catching malformed or
+    /// unexpected input up front
+    #[cfg(feature = "backend-object")]
+    #[arg(long, default_value = "builtin")]
+    #[arg(value_parser = ["builtin", "object"])]
+    backend: String,
+
     /// Force processing of files without GLOBAL_ASM blocks
     #[arg(long)]
     force: bool,
@@ -135,11 +181,22 @@ pub fn run_wrapped(args: Args, outfile: Option<&mut dyn Write>) -> Result<(Vec<F
         &args.input_enc,
         &args.output_enc,
         args.encode_cutscene_data_floats,
+        &args.arch,
     );
 
+    let profile = arch::from_name(&args.arch)?;
+
     if let Some(objfile) = args.post_process {
-        if args.assembler.is_none() {
-            return Err(Error::InvalidInput("must pass assembler command".into()));
+        let assembler = match &args.assembler {
+            Some(assembler) => assembler.clone(),
+            None => profile.default_assembler().to_string(),
+        };
+
+        #[cfg(feature = "backend-object")]
+        if args.backend == "object" && !objfile.extension().is_some_and(|ext| ext == "a") {
+            // Validate the file through `object` up front; `fixup_objfile`
+            // below still does its own (hand-rolled) parse.
+            asm_processor::elf::read_elf_file(&objfile)?;
         }
 
         let functions = {
@@ -157,15 +214,51 @@ pub fn run_wrapped(args: Args, outfile: Option<&mut dyn Write>) -> Result<(Vec<F
             Vec::new()
         };
 
-        fixup_objfile(
-            &objfile,
-            &functions,
-            &asm_prelude,
-            args.assembler.as_ref().unwrap(),
-            &args.output_enc,
-            args.drop_mdebug_gptab,
-            &args.convert_statics,
-        )?;
+        let symbol_overrides = if let Some(symbols_path) = &args.symbols {
+            parse_symbol_overrides(symbols_path)?
+        } else {
+            Default::default()
+        };
+
+        let symbol_map = if let Some(symbol_map_path) = &args.symbol_map {
+            parse_linker_map(symbol_map_path)?
+        } else {
+            Default::default()
+        };
+
+        // A `.a` archive bundles multiple object files; post-process every
+        // ELF member in place instead of treating the path as a lone `.o`.
+        if objfile.extension().is_some_and(|ext| ext == "a") {
+            fixup_archive(
+                &objfile,
+                &functions,
+                &asm_prelude,
+                &assembler,
+                &args.output_enc,
+                args.drop_mdebug_gptab,
+                args.drop_comment,
+                &args.convert_statics,
+                &symbol_overrides,
+                &symbol_map,
+                profile.as_ref(),
+                args.split_meta,
+            )?;
+        } else {
+            fixup_objfile(
+                &objfile,
+                &functions,
+                &asm_prelude,
+                &assembler,
+                &args.output_enc,
+                args.drop_mdebug_gptab,
+                args.drop_comment,
+                &args.convert_statics,
+                &symbol_overrides,
+                &symbol_map,
+                profile.as_ref(),
+                args.split_meta,
+            )?;
+        }
 
         Ok((functions, Vec::new()))
     } else {
@@ -187,7 +280,8 @@ pub fn run_wrapped(args: Args, outfile: Option<&mut dyn Write>) -> Result<(Vec<F
 ///
 /// This is the main entry point for the command line interface.
 pub fn run(argv: &[String], outfile: Option<&mut dyn Write>, functions: Option<Vec<Function>>) -> Result<(Vec<Function>, Vec<String>)> {
-    let args = Args::try_parse_from(argv)
+    let argv = merge_env_opts(argv);
+    let args = Args::try_parse_from(&argv)
         .map_err(|e| Error::InvalidInput(e.to_string()))?;
     run_wrapped(args, outfile)
 }
@@ -196,7 +290,12 @@ fn main() {
     match run(&std::env::args().collect::<Vec<_>>(), Some(&mut io::stdout()), None) {
         Ok(_) => (),
         Err(e) => {
-            eprintln!("Error: {}", e);
+            // Errors with source-location context print in the usual
+            // `path:line: error: ...` compiler-diagnostic style.
+            match &e {
+                Error::WithLocation { source, location } => eprintln!("{}: error: {}", location, source),
+                other => eprintln!("error: {}", other),
+            }
             std::process::exit(1);
         }
     }
@@ -0,0 +1,279 @@
+//! COFF (Common Object File Format) section/symbol model, a sibling of the
+//! ELF backend for Windows-hosted decomp targets (e.g. `mips-coff`/
+//! Metrowerks-style toolchains). [`CoffSection`] plays the same role
+//! [`crate::elf::section::ElfSection`] plays for ELF and implements the
+//! same [`Section`] trait, so the front-end that discovers injected
+//! functions doesn't need to care which backend eventually emits them.
+
+use crate::elf::constants::{R_MIPS_26, R_MIPS_32, R_MIPS_HI16, R_MIPS_LO16};
+use crate::elf::section::Section;
+use crate::utils::Error;
+
+/// `IMAGE_RELOCATION.Type` values this crate knows how to produce from the
+/// MIPS ELF relocation types [`Relocation`](crate::elf::relocation::Relocation)
+/// already parses.
+pub mod reloc_type {
+    pub const IMAGE_REL_MIPS_REFWORD: u16 = 0x0002;
+    pub const IMAGE_REL_MIPS_JMPADDR: u16 = 0x0003;
+    pub const IMAGE_REL_MIPS_REFHI: u16 = 0x0004;
+    pub const IMAGE_REL_MIPS_REFLO: u16 = 0x0005;
+}
+
+/// Translate a MIPS ELF relocation type (`R_MIPS_*`) to its COFF
+/// equivalent, for the subset [`crate::elf::section::ElfSection::apply_relocations`]
+/// also understands. `None` for anything else.
+pub fn mips_reloc_to_coff(rel_type: u32) -> Option<u16> {
+    match rel_type {
+        R_MIPS_32 => Some(reloc_type::IMAGE_REL_MIPS_REFWORD),
+        R_MIPS_26 => Some(reloc_type::IMAGE_REL_MIPS_JMPADDR),
+        R_MIPS_HI16 => Some(reloc_type::IMAGE_REL_MIPS_REFHI),
+        R_MIPS_LO16 => Some(reloc_type::IMAGE_REL_MIPS_REFLO),
+        _ => None,
+    }
+}
+
+/// A single COFF relocation entry (`IMAGE_RELOCATION`, 10 bytes):
+/// `virtual_address`, `symbol_table_index`, `type_`. Always little-endian,
+/// unlike ELF's format-dependent endianness.
+#[derive(Debug, Clone)]
+pub struct CoffRelocation {
+    pub virtual_address: u32,
+    pub symbol_table_index: u32,
+    pub type_: u16,
+}
+
+impl CoffRelocation {
+    pub const SIZE: usize = 10;
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(Self::SIZE);
+        out.extend_from_slice(&self.virtual_address.to_le_bytes());
+        out.extend_from_slice(&self.symbol_table_index.to_le_bytes());
+        out.extend_from_slice(&self.type_.to_le_bytes());
+        out
+    }
+}
+
+/// A COFF section header (`IMAGE_SECTION_HEADER`, 40 bytes) plus its raw
+/// data and relocations, mirroring the role
+/// [`crate::elf::section::ElfSection`] plays for the ELF backend.
+#[derive(Debug, Clone, Default)]
+pub struct CoffSection {
+    pub name: String,
+    pub virtual_size: u32,
+    pub virtual_address: u32,
+    pub characteristics: u32,
+    pub data: Vec<u8>,
+    pub relocations: Vec<CoffRelocation>,
+    pub index: usize,
+}
+
+impl CoffSection {
+    pub const HEADER_SIZE: usize = 40;
+
+    pub fn new(name: &str, characteristics: u32, data: Vec<u8>, index: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            virtual_size: data.len() as u32,
+            virtual_address: 0,
+            characteristics,
+            data,
+            relocations: Vec::new(),
+            index,
+        }
+    }
+
+    /// Pack this section's `IMAGE_SECTION_HEADER`. `short_name`,
+    /// `raw_data_ptr` and `reloc_ptr` are precomputed by [`write_coff`]:
+    /// COFF stores section data/relocation locations as plain file
+    /// offsets, which only the writer knows once the layout is final.
+    pub fn to_bytes(&self, short_name: &[u8; 8], raw_data_ptr: u32, reloc_ptr: u32) -> Vec<u8> {
+        let mut out = Vec::with_capacity(Self::HEADER_SIZE);
+        out.extend_from_slice(short_name);
+        out.extend_from_slice(&self.virtual_size.to_le_bytes());
+        out.extend_from_slice(&self.virtual_address.to_le_bytes());
+        out.extend_from_slice(&(self.data.len() as u32).to_le_bytes());
+        out.extend_from_slice(&raw_data_ptr.to_le_bytes());
+        out.extend_from_slice(&reloc_ptr.to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes()); // pointer_to_linenumbers
+        out.extend_from_slice(&(self.relocations.len().min(0xffff) as u16).to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes()); // number_of_linenumbers
+        out.extend_from_slice(&self.characteristics.to_le_bytes());
+        out
+    }
+}
+
+impl Section for CoffSection {
+    /// COFF has no string-table *sections* of its own; long names live in
+    /// the object's single trailing string table instead (see
+    /// [`pack_short_name`]). This treats `self.data` as that blob, the
+    /// same way an ELF `SHT_STRTAB` section's `data` is used, for callers
+    /// that want to build a COFF string table incrementally.
+    fn lookup_str(&self, offset: u32) -> String {
+        let offset = offset as usize;
+        let end = self.data[offset..]
+            .iter()
+            .position(|&b| b == 0)
+            .map(|p| offset + p)
+            .unwrap_or(self.data.len());
+        self.data[offset..end].iter().map(|&b| b as char).collect()
+    }
+
+    fn add_str(&mut self, s: &str) -> Result<u32, Error> {
+        let ret = self.data.len() as u32;
+        self.data.extend(s.chars().map(|c| c as u8));
+        self.data.push(0);
+        Ok(ret)
+    }
+}
+
+/// A COFF symbol table entry (`IMAGE_SYMBOL`, 18 bytes).
+#[derive(Debug, Clone)]
+pub struct CoffSymbol {
+    pub name: String,
+    pub value: u32,
+    pub section_number: i16,
+    pub type_: u16,
+    pub storage_class: u8,
+    pub number_of_aux_symbols: u8,
+}
+
+impl CoffSymbol {
+    pub const SIZE: usize = 18;
+
+    /// Pack this symbol, given `short_name` precomputed the same way as
+    /// [`CoffSection::to_bytes`]'s.
+    pub fn to_bytes(&self, short_name: &[u8; 8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(Self::SIZE);
+        out.extend_from_slice(short_name);
+        out.extend_from_slice(&self.value.to_le_bytes());
+        out.extend_from_slice(&self.section_number.to_le_bytes());
+        out.extend_from_slice(&self.type_.to_le_bytes());
+        out.push(self.storage_class);
+        out.push(self.number_of_aux_symbols);
+        out
+    }
+}
+
+/// Pack `name` into an inline 8-byte COFF short name if it fits, or a
+/// `0, offset` string-table reference (first 4 bytes zero, next 4 the
+/// little-endian byte offset into the string table, which itself starts
+/// with its own 4-byte total-length prefix) otherwise, appending the name
+/// to `strtab` when it doesn't.
+pub fn pack_short_name(name: &str, strtab: &mut Vec<u8>) -> [u8; 8] {
+    let bytes = name.as_bytes();
+    let mut out = [0u8; 8];
+    if bytes.len() <= 8 {
+        out[..bytes.len()].copy_from_slice(bytes);
+    } else {
+        let offset = strtab.len() as u32 + 4;
+        strtab.extend_from_slice(bytes);
+        strtab.push(0);
+        out[4..8].copy_from_slice(&offset.to_le_bytes());
+    }
+    out
+}
+
+/// Assemble a full COFF object file (`IMAGE_FILE_HEADER` + section headers
+/// + section data/relocations + symbol table + string table) from already-
+/// built sections and symbols, mirroring [`crate::elf::file::ElfFile::write`]'s
+/// role for the ELF backend.
+pub fn write_coff(machine: u16, sections: &[CoffSection], symbols: &[CoffSymbol]) -> Vec<u8> {
+    let mut strtab = Vec::new();
+    let short_section_names: Vec<[u8; 8]> = sections.iter().map(|s| pack_short_name(&s.name, &mut strtab)).collect();
+    let short_symbol_names: Vec<[u8; 8]> = symbols.iter().map(|s| pack_short_name(&s.name, &mut strtab)).collect();
+
+    const FILE_HEADER_SIZE: usize = 20;
+    let mut data_ptr = (FILE_HEADER_SIZE + sections.len() * CoffSection::HEADER_SIZE) as u32;
+
+    let mut raw_data_ptrs = Vec::with_capacity(sections.len());
+    let mut reloc_ptrs = Vec::with_capacity(sections.len());
+    for section in sections {
+        raw_data_ptrs.push(data_ptr);
+        data_ptr += section.data.len() as u32;
+        if section.relocations.is_empty() {
+            reloc_ptrs.push(0);
+        } else {
+            reloc_ptrs.push(data_ptr);
+            data_ptr += (section.relocations.len() * CoffRelocation::SIZE) as u32;
+        }
+    }
+    let symtab_ptr = data_ptr;
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&machine.to_le_bytes());
+    out.extend_from_slice(&(sections.len() as u16).to_le_bytes());
+    out.extend_from_slice(&0u32.to_le_bytes()); // time_date_stamp
+    out.extend_from_slice(&symtab_ptr.to_le_bytes());
+    out.extend_from_slice(&(symbols.len() as u32).to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes()); // size_of_optional_header
+    out.extend_from_slice(&0u16.to_le_bytes()); // characteristics
+
+    for (i, section) in sections.iter().enumerate() {
+        out.extend_from_slice(&section.to_bytes(&short_section_names[i], raw_data_ptrs[i], reloc_ptrs[i]));
+    }
+    for section in sections {
+        out.extend_from_slice(&section.data);
+        for reloc in &section.relocations {
+            out.extend_from_slice(&reloc.to_bytes());
+        }
+    }
+    for (i, symbol) in symbols.iter().enumerate() {
+        out.extend_from_slice(&symbol.to_bytes(&short_symbol_names[i]));
+    }
+    out.extend_from_slice(&(strtab.len() as u32 + 4).to_le_bytes());
+    out.extend_from_slice(&strtab);
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mips_reloc_to_coff() {
+        assert_eq!(mips_reloc_to_coff(R_MIPS_32), Some(reloc_type::IMAGE_REL_MIPS_REFWORD));
+        assert_eq!(mips_reloc_to_coff(R_MIPS_HI16), Some(reloc_type::IMAGE_REL_MIPS_REFHI));
+        assert_eq!(mips_reloc_to_coff(0xdead), None);
+    }
+
+    #[test]
+    fn test_pack_short_name_inline() {
+        let mut strtab = Vec::new();
+        let packed = pack_short_name(".text", &mut strtab);
+        assert_eq!(&packed[..5], b".text");
+        assert_eq!(&packed[5..], &[0, 0, 0]);
+        assert!(strtab.is_empty());
+    }
+
+    #[test]
+    fn test_pack_short_name_overflow() {
+        let mut strtab = Vec::new();
+        let packed = pack_short_name("a_much_longer_section_name", &mut strtab);
+        assert_eq!(&packed[..4], &[0, 0, 0, 0]);
+        let offset = u32::from_le_bytes(packed[4..8].try_into().unwrap());
+        assert_eq!(offset, 4);
+        assert_eq!(&strtab[..26], b"a_much_longer_section_name");
+        assert_eq!(strtab[26], 0);
+    }
+
+    #[test]
+    fn test_write_coff_roundtrip_header() {
+        let section = CoffSection::new(".text", 0x20, vec![1, 2, 3, 4], 0);
+        let symbol = CoffSymbol {
+            name: "main".to_string(),
+            value: 0,
+            section_number: 1,
+            type_: 0x20,
+            storage_class: 2,
+            number_of_aux_symbols: 0,
+        };
+
+        let data = write_coff(0x0162 /* IMAGE_FILE_MACHINE_R4000 */, &[section], &[symbol]);
+
+        assert_eq!(u16::from_le_bytes(data[0..2].try_into().unwrap()), 0x0162);
+        assert_eq!(u16::from_le_bytes(data[2..4].try_into().unwrap()), 1); // number of sections
+        assert_eq!(u32::from_le_bytes(data[12..16].try_into().unwrap()), 1); // number of symbols
+    }
+}
@@ -0,0 +1,352 @@
+use std::collections::HashMap;
+
+use crate::elf::constants::*;
+use crate::elf::file::ElfFile;
+use crate::elf::format::ElfFormat;
+use crate::elf::header::ElfHeader;
+use crate::elf::section::{ElfSection, Section};
+use crate::utils::Error;
+
+/// A stable identifier for a section held by an [`ElfBuilder`]. Unlike
+/// [`ElfSection::index`], a handle stays valid across [`ElfBuilder::add_section`]
+/// and [`ElfBuilder::drop_section`] calls, even when those shift every
+/// section after the affected one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SectionHandle(usize);
+
+/// Wraps an [`ElfFile`] and lets sections be added or dropped by stable
+/// [`SectionHandle`] instead of raw index, following the model of object's
+/// `copy/elf` Builder. [`ElfBuilder::write`] recomputes every index-bearing
+/// field (`sh_link`/`sh_info` on symbol-table and relocation sections,
+/// each symbol's `st_shndx`, and `e_shstrndx`) against the final section
+/// order before re-serializing, so insertions and deletions in the middle
+/// of the section list no longer corrupt cross-references.
+pub struct ElfBuilder {
+    fmt: ElfFormat,
+    header: ElfHeader,
+    order: Vec<SectionHandle>,
+    sections: HashMap<SectionHandle, ElfSection>,
+    next_id: usize,
+    /// The numeric section index each handle had when it was read from the
+    /// file (sections added later have no entry), i.e. the index any `sh_link`/`sh_info`/`st_shndx`
+    /// referring to it was written against. Used to remap those fields to
+    /// the section's current position in `order`.
+    orig_index: HashMap<SectionHandle, usize>,
+}
+
+impl ElfBuilder {
+    pub fn from_file(file: ElfFile) -> Self {
+        let mut order = Vec::with_capacity(file.sections.len());
+        let mut sections = HashMap::with_capacity(file.sections.len());
+        let mut orig_index = HashMap::with_capacity(file.sections.len());
+
+        for (i, section) in file.sections.into_iter().enumerate() {
+            let handle = SectionHandle(i);
+            order.push(handle);
+            orig_index.insert(handle, i);
+            sections.insert(handle, section);
+        }
+
+        Self {
+            fmt: file.fmt,
+            header: file.header,
+            next_id: order.len(),
+            order,
+            sections,
+            orig_index,
+        }
+    }
+
+    pub fn section(&self, handle: SectionHandle) -> &ElfSection {
+        &self.sections[&handle]
+    }
+
+    pub fn section_mut(&mut self, handle: SectionHandle) -> &mut ElfSection {
+        self.sections.get_mut(&handle).expect("invalid SectionHandle")
+    }
+
+    pub fn find_handle(&self, name: &str) -> Option<SectionHandle> {
+        self.order.iter().copied().find(|h| self.sections[h].name == name)
+    }
+
+    /// Append a new section, naming it via the `.shstrtab` section (found
+    /// by name). `sh_link`/`sh_info` are taken as raw section indices, same
+    /// as [`ElfSection::from_parts`]. [`Self::write`] remaps `sh_link` only
+    /// on symbol-table and relocation sections, and `sh_info` only on
+    /// relocation sections, and only where the index refers to a section
+    /// that was present when the builder was created; otherwise they are
+    /// written as given.
+    pub fn add_section(
+        &mut self,
+        name: &str,
+        sh_type: u32,
+        sh_flags: u64,
+        sh_link: u32,
+        sh_info: u32,
+        sh_addralign: u64,
+        sh_entsize: u64,
+        data: Vec<u8>,
+    ) -> Result<SectionHandle, Error> {
+        let shstrtab = self
+            .find_handle(".shstrtab")
+            .ok_or_else(|| Error::InvalidSection("No .shstrtab section".into()))?;
+        let sh_name = self.section_mut(shstrtab).add_str(name)?;
+
+        let handle = SectionHandle(self.next_id);
+        self.next_id += 1;
+
+        let section = ElfSection::from_parts(self.fmt, sh_name, sh_type, sh_flags, sh_link, sh_info, sh_addralign, sh_entsize, data, 0);
+        self.sections.insert(handle, section);
+        self.order.push(handle);
+
+        Ok(handle)
+    }
+
+    /// Remove a section. Any other section's `sh_link`/`sh_info` or
+    /// symbol's `st_shndx` that referred to it will be left pointing at
+    /// whatever now occupies its old numeric index; callers are expected
+    /// to only drop sections nothing else still references.
+    pub fn drop_section(&mut self, handle: SectionHandle) {
+        self.order.retain(|&h| h != handle);
+        self.sections.remove(&handle);
+        self.orig_index.remove(&handle);
+    }
+
+    /// Change the name of the `symbol_index`-th entry of `symtab`'s symbol
+    /// table, appending the new name to `strtab` (found by name) rather
+    /// than mutating any existing string in place.
+    pub fn redefine_symbol_name(
+        &mut self,
+        symtab: SectionHandle,
+        symbol_index: usize,
+        strtab: &str,
+        new_name: &str,
+    ) -> Result<(), Error> {
+        let strtab_handle = self
+            .find_handle(strtab)
+            .ok_or_else(|| Error::InvalidSection(format!("No {} section", strtab)))?;
+        let st_name = self.section_mut(strtab_handle).add_str(new_name)?;
+
+        let symtab_section = self.section_mut(symtab);
+        let symbol = symtab_section
+            .symbols
+            .get_mut(symbol_index)
+            .ok_or_else(|| Error::InvalidSymbol(format!("No symbol at index {}", symbol_index)))?;
+        symbol.set_name(st_name, new_name.to_string());
+        symtab_section.data = symtab_section.symbols.iter().flat_map(|s| s.to_bytes()).collect();
+        Ok(())
+    }
+
+    /// Find a symbol named `old_name` in `symtab` and rename it to
+    /// `new_name`, as [`Self::redefine_symbol_name`]. Errors if no such
+    /// symbol exists.
+    pub fn rename_symbol(
+        &mut self,
+        symtab: SectionHandle,
+        strtab: &str,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<(), Error> {
+        let symbol_index = self
+            .section(symtab)
+            .symbols
+            .iter()
+            .position(|s| s.name == old_name)
+            .ok_or_else(|| Error::InvalidSymbol(format!("No symbol named {}", old_name)))?;
+        self.redefine_symbol_name(symtab, symbol_index, strtab, new_name)
+    }
+
+    /// Drop the trailing run of `.mdebug`/`.gptab` sections, same as the
+    /// old `ElfFile::drop_mdebug_gptab`.
+    pub fn drop_mdebug_gptab(&mut self) {
+        while let Some(&handle) = self.order.last() {
+            let sh_type = self.sections[&handle].sh_type;
+            if sh_type != SHT_MIPS_DEBUG && sh_type != SHT_MIPS_GPTAB {
+                break;
+            }
+            self.drop_section(handle);
+        }
+    }
+
+    /// Recompute every index-bearing field against the current section
+    /// order and serialize to `filename`.
+    pub fn write(&mut self, filename: &str) -> Result<(), Error> {
+        self.build_file()?.write(filename)
+    }
+
+    /// As [`Self::write`], but serializes to an in-memory buffer instead of
+    /// a path on disk.
+    pub fn write_to_vec(&mut self) -> Result<Vec<u8>, Error> {
+        self.build_file()?.write_to_vec()
+    }
+
+    /// Recompute every index-bearing field against the current section
+    /// order and flatten back into a plain [`ElfFile`] ready to be written.
+    fn build_file(&mut self) -> Result<ElfFile, Error> {
+        let new_index: HashMap<SectionHandle, usize> =
+            self.order.iter().enumerate().map(|(i, &h)| (h, i)).collect();
+
+        // Old numeric index -> new numeric index, for fields that were
+        // written against the section list's layout at some earlier point.
+        let remap: HashMap<usize, usize> = self
+            .orig_index
+            .iter()
+            .filter_map(|(h, &old)| new_index.get(h).map(|&new| (old, new)))
+            .collect();
+
+        let shstrtab = self
+            .find_handle(".shstrtab")
+            .ok_or_else(|| Error::InvalidSection("No .shstrtab section".into()))?;
+        self.header.e_shstrndx = new_index[&shstrtab] as u16;
+
+        let mut symtab_index = None;
+        let mut sections = Vec::with_capacity(self.order.len());
+        for &handle in &self.order {
+            let mut section = self.sections[&handle].clone();
+            section.index = new_index[&handle];
+
+            if section.sh_type == SHT_SYMTAB || section.is_rel() {
+                if let Some(&new_link) = remap.get(&(section.sh_link as usize)) {
+                    section.sh_link = new_link as u32;
+                }
+            }
+            if section.is_rel() {
+                if let Some(&new_info) = remap.get(&(section.sh_info as usize)) {
+                    section.sh_info = new_info as u32;
+                }
+            }
+            if section.sh_type == SHT_SYMTAB {
+                symtab_index = Some(section.index);
+                for symbol in &mut section.symbols {
+                    let old_shndx = symbol.shndx32 as usize;
+                    if old_shndx < SHN_LORESERVE as usize {
+                        if let Some(&new_shndx) = remap.get(&old_shndx) {
+                            symbol.set_shndx(new_shndx as u32);
+                        }
+                    }
+                }
+                // Re-encode the remapped symbols back into the section's
+                // raw bytes, which is what actually gets written out.
+                section.data = section.symbols.iter().flat_map(|s| s.to_bytes()).collect();
+            }
+
+            sections.push(section);
+        }
+
+        let symtab = symtab_index.ok_or_else(|| Error::InvalidFormat("No symbol table found".into()))?;
+        Ok(ElfFile {
+            header: self.header.clone(),
+            sections,
+            fmt: self.fmt,
+            symtab,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elf::symbol::Symbol;
+    use std::fs;
+
+    fn minimal_header(fmt: ElfFormat) -> ElfHeader {
+        let mut h = vec![0u8; 52];
+        h[0..4].copy_from_slice(b"\x7fELF");
+        h[EI_CLASS] = 1; // 32-bit
+        h[EI_DATA] = 2; // big-endian
+        h[EI_VERSION] = 1; // EV_CURRENT
+        h[16..18].copy_from_slice(&fmt.pack_u16(1)); // e_type
+        h[18..20].copy_from_slice(&fmt.pack_u16(8)); // e_machine (EM_MIPS)
+        h[32..36].copy_from_slice(&fmt.pack_u32(1)); // e_shoff (non-zero placeholder)
+        h[46..48].copy_from_slice(&fmt.pack_u16(40)); // e_shentsize
+        h[50..52].copy_from_slice(&fmt.pack_u16(1)); // e_shstrndx
+        ElfHeader::new(&h).unwrap()
+    }
+
+    /// Builds a minimal null/.shstrtab/.data/.bss/.symtab file where the
+    /// lone symbol points at `.bss`, drops the middle `.data` section, and
+    /// checks that after `write` the section headers and the symbol's
+    /// `st_shndx` all agree on `.bss`'s new position.
+    #[test]
+    fn test_drop_section_remaps_links_and_symbols() {
+        let fmt = ElfFormat::new(true);
+
+        let null_section = ElfSection::default();
+        let shstrtab = ElfSection {
+            fmt,
+            name: ".shstrtab".to_string(),
+            sh_name: 1,
+            sh_type: SHT_STRTAB,
+            data: b"\0.shstrtab\0.data\0.bss\0".to_vec(),
+            ..ElfSection::default()
+        };
+        let data_section = ElfSection { fmt, name: ".data".to_string(), sh_type: SHT_PROGBITS, sh_name: 11, ..ElfSection::default() };
+        let bss_section = ElfSection { fmt, name: ".bss".to_string(), sh_type: SHT_NOBITS, sh_name: 17, ..ElfSection::default() };
+        let mut symtab_section = ElfSection { fmt, sh_type: SHT_SYMTAB, sh_link: 1, sh_entsize: 16, ..ElfSection::default() };
+        // A symbol pointing at .bss, which is section index 3 before the drop.
+        let sym = Symbol::from_parts(fmt, 0, 0, 0, 0, 0, 3, &shstrtab, "bss_sym".to_string()).unwrap();
+        symtab_section.symbols.push(sym);
+
+        let file = ElfFile {
+            header: minimal_header(fmt),
+            sections: vec![null_section, shstrtab, data_section, bss_section, symtab_section],
+            fmt,
+            symtab: 4,
+        };
+
+        let mut builder = ElfBuilder::from_file(file);
+        let data_handle = builder.find_handle(".data").unwrap();
+        builder.drop_section(data_handle);
+
+        let temp_file = "test_builder_drop_section.tmp";
+        builder.write(temp_file).unwrap();
+
+        let data = fs::read(temp_file).unwrap();
+        let written = ElfFile::new(&data).unwrap();
+        fs::remove_file(temp_file).unwrap();
+
+        // .bss (originally index 3) is now index 2; the symbol should follow it.
+        let bss = written.find_section(".bss").unwrap();
+        assert_eq!(bss.index, 2);
+        assert_eq!(written.sections[written.symtab].symbols[0].shndx32, bss.index as u32);
+    }
+
+    #[test]
+    fn test_rename_symbol() {
+        let fmt = ElfFormat::new(true);
+
+        let null_section = ElfSection::default();
+        let shstrtab = ElfSection {
+            fmt,
+            name: ".shstrtab".to_string(),
+            sh_name: 1,
+            sh_type: SHT_STRTAB,
+            data: b"\0.shstrtab\0.strtab\0".to_vec(),
+            ..ElfSection::default()
+        };
+        let strtab = ElfSection {
+            fmt,
+            name: ".strtab".to_string(),
+            sh_name: 11,
+            sh_type: SHT_STRTAB,
+            data: b"\0old_name\0".to_vec(),
+            ..ElfSection::default()
+        };
+        let mut symtab_section = ElfSection { fmt, sh_type: SHT_SYMTAB, sh_link: 2, sh_entsize: 16, ..ElfSection::default() };
+        let sym = Symbol::from_parts(fmt, 1, 0, 0, 0, 0, 0, &strtab, "old_name".to_string()).unwrap();
+        symtab_section.symbols.push(sym);
+
+        let file = ElfFile {
+            header: minimal_header(fmt),
+            sections: vec![null_section, shstrtab, strtab, symtab_section],
+            fmt,
+            symtab: 3,
+        };
+
+        let mut builder = ElfBuilder::from_file(file);
+        let symtab_handle = *builder.order.iter().find(|&&h| builder.section(h).sh_type == SHT_SYMTAB).unwrap();
+        builder.rename_symbol(symtab_handle, ".strtab", "old_name", "new_name").unwrap();
+
+        assert_eq!(builder.section(symtab_handle).symbols[0].name, "new_name");
+    }
+}
@@ -7,6 +7,16 @@ pub const EI_OSABI: usize = 7;
 pub const EI_ABIVERSION: usize = 8;
 pub const STN_UNDEF: u32 = 0;
 
+// e_ident[EI_CLASS] values
+pub const ELFCLASSNONE: u8 = 0;
+pub const ELFCLASS32: u8 = 1;
+pub const ELFCLASS64: u8 = 2;
+
+// e_ident[EI_DATA] values
+pub const ELFDATANONE: u8 = 0;
+pub const ELFDATA2LSB: u8 = 1;
+pub const ELFDATA2MSB: u8 = 2;
+
 // Section Header constants
 pub const SHN_UNDEF: u16 = 0;
 pub const SHN_ABS: u16 = 0xfff1;
@@ -68,6 +78,11 @@ pub const SHF_LINK_ORDER: u32 = 0x80;
 pub const SHF_OS_NONCONFORMING: u32 = 0x100;
 pub const SHF_GROUP: u32 = 0x200;
 pub const SHF_TLS: u32 = 0x400;
+pub const SHF_COMPRESSED: u32 = 0x800;
+
+// Elf_Chdr::ch_type values
+pub const ELFCOMPRESS_ZLIB: u32 = 1;
+pub const ELFCOMPRESS_ZSTD: u32 = 2;
 
 // MIPS Relocation Types
 pub const R_MIPS_32: u32 = 2;
@@ -75,6 +90,37 @@ pub const R_MIPS_26: u32 = 4;
 pub const R_MIPS_HI16: u32 = 5;
 pub const R_MIPS_LO16: u32 = 6;
 
+// PowerPC Relocation Types (EABI, e.g. GameCube/Wii)
+pub const R_PPC_ADDR32: u32 = 1;
+pub const R_PPC_ADDR24: u32 = 2;
+pub const R_PPC_ADDR16: u32 = 3;
+pub const R_PPC_ADDR16_HI: u32 = 5;
+pub const R_PPC_ADDR16_HA: u32 = 6;
+pub const R_PPC_REL24: u32 = 10;
+pub const R_PPC_REL14: u32 = 11;
+
+// MIPS ELF header `e_flags` bit fields
+pub const EF_MIPS_NOREORDER: u32 = 0x00000001;
+pub const EF_MIPS_PIC: u32 = 0x00000002;
+pub const EF_MIPS_CPIC: u32 = 0x00000004;
+pub const EF_MIPS_XGOT: u32 = 0x00000008;
+pub const EF_MIPS_32BITMODE: u32 = 0x00000100;
+pub const EF_MIPS_ABI: u32 = 0x0000f000;
+pub const EF_MIPS_ABI_O32: u32 = 0x00001000;
+pub const EF_MIPS_ABI_O64: u32 = 0x00002000;
+pub const EF_MIPS_ABI_EABI32: u32 = 0x00003000;
+pub const EF_MIPS_ABI_EABI64: u32 = 0x00004000;
+pub const EF_MIPS_ARCH: u32 = 0xf0000000;
+pub const EF_MIPS_ARCH_1: u32 = 0x00000000;
+pub const EF_MIPS_ARCH_2: u32 = 0x10000000;
+pub const EF_MIPS_ARCH_3: u32 = 0x20000000;
+pub const EF_MIPS_ARCH_4: u32 = 0x30000000;
+pub const EF_MIPS_ARCH_5: u32 = 0x40000000;
+pub const EF_MIPS_ARCH_32: u32 = 0x50000000;
+pub const EF_MIPS_ARCH_64: u32 = 0x60000000;
+pub const EF_MIPS_ARCH_32R2: u32 = 0x70000000;
+pub const EF_MIPS_ARCH_64R2: u32 = 0x80000000;
+
 // MIPS Debug Constants
 pub const MIPS_DEBUG_ST_STATIC: u32 = 2;
 pub const MIPS_DEBUG_ST_PROC: u32 = 6;
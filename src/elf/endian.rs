@@ -0,0 +1,156 @@
+//! Zero-sized, compile-time endianness markers, following the `object`
+//! crate's `endian` module: [`BigEndian`] and [`LittleEndian`] implement
+//! [`Endian`] with no runtime branching, so a parser generic over `E: Endian`
+//! has its byte-swap decision monomorphized away entirely. [`ElfFormat`]
+//! stays the dynamic entry point for call sites that only learn the
+//! endianness at runtime (parsing `EI_DATA` out of an untrusted `e_ident`);
+//! it dispatches to these same trait methods through the [`Endianness`]
+//! enum, so there's exactly one implementation of each byte-swap to keep in
+//! sync.
+//!
+//! [`ElfFormat`]: crate::elf::format::ElfFormat
+
+/// A compile-time-known byte order: [`LittleEndian`] or [`BigEndian`].
+/// Implementors are zero-sized, so a type generic over `E: Endian` pays
+/// nothing at runtime for the endianness check `ElfFormat` has to make on
+/// every field.
+pub trait Endian: Copy + Clone + Default + std::fmt::Debug + 'static {
+    fn read_u16(bytes: [u8; 2]) -> u16;
+    fn read_u32(bytes: [u8; 4]) -> u32;
+    fn read_u64(bytes: [u8; 8]) -> u64;
+    fn write_u16(value: u16) -> [u8; 2];
+    fn write_u32(value: u32) -> [u8; 4];
+    fn write_u64(value: u64) -> [u8; 8];
+}
+
+/// Little-endian byte order (MIPS EL, x86, ...).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LittleEndian;
+
+impl Endian for LittleEndian {
+    fn read_u16(bytes: [u8; 2]) -> u16 {
+        u16::from_le_bytes(bytes)
+    }
+
+    fn read_u32(bytes: [u8; 4]) -> u32 {
+        u32::from_le_bytes(bytes)
+    }
+
+    fn read_u64(bytes: [u8; 8]) -> u64 {
+        u64::from_le_bytes(bytes)
+    }
+
+    fn write_u16(value: u16) -> [u8; 2] {
+        value.to_le_bytes()
+    }
+
+    fn write_u32(value: u32) -> [u8; 4] {
+        value.to_le_bytes()
+    }
+
+    fn write_u64(value: u64) -> [u8; 8] {
+        value.to_le_bytes()
+    }
+}
+
+/// Big-endian byte order (MIPS EB, PowerPC, ...) — the default for this
+/// crate's primary decompilation targets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BigEndian;
+
+impl Endian for BigEndian {
+    fn read_u16(bytes: [u8; 2]) -> u16 {
+        u16::from_be_bytes(bytes)
+    }
+
+    fn read_u32(bytes: [u8; 4]) -> u32 {
+        u32::from_be_bytes(bytes)
+    }
+
+    fn read_u64(bytes: [u8; 8]) -> u64 {
+        u64::from_be_bytes(bytes)
+    }
+
+    fn write_u16(value: u16) -> [u8; 2] {
+        value.to_be_bytes()
+    }
+
+    fn write_u32(value: u32) -> [u8; 4] {
+        value.to_be_bytes()
+    }
+
+    fn write_u64(value: u64) -> [u8; 8] {
+        value.to_be_bytes()
+    }
+}
+
+/// A runtime choice between [`LittleEndian`] and [`BigEndian`], for the
+/// `ElfFormat` path where the byte order isn't known until `e_ident[EI_DATA]`
+/// has been read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+impl Endianness {
+    pub fn read_u16(self, bytes: [u8; 2]) -> u16 {
+        match self {
+            Endianness::Little => LittleEndian::read_u16(bytes),
+            Endianness::Big => BigEndian::read_u16(bytes),
+        }
+    }
+
+    pub fn read_u32(self, bytes: [u8; 4]) -> u32 {
+        match self {
+            Endianness::Little => LittleEndian::read_u32(bytes),
+            Endianness::Big => BigEndian::read_u32(bytes),
+        }
+    }
+
+    pub fn read_u64(self, bytes: [u8; 8]) -> u64 {
+        match self {
+            Endianness::Little => LittleEndian::read_u64(bytes),
+            Endianness::Big => BigEndian::read_u64(bytes),
+        }
+    }
+
+    pub fn write_u16(self, value: u16) -> [u8; 2] {
+        match self {
+            Endianness::Little => LittleEndian::write_u16(value),
+            Endianness::Big => BigEndian::write_u16(value),
+        }
+    }
+
+    pub fn write_u32(self, value: u32) -> [u8; 4] {
+        match self {
+            Endianness::Little => LittleEndian::write_u32(value),
+            Endianness::Big => BigEndian::write_u32(value),
+        }
+    }
+
+    pub fn write_u64(self, value: u64) -> [u8; 8] {
+        match self {
+            Endianness::Little => LittleEndian::write_u64(value),
+            Endianness::Big => BigEndian::write_u64(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typed_endian_roundtrip() {
+        assert_eq!(LittleEndian::read_u32(LittleEndian::write_u32(0x1234_5678)), 0x1234_5678);
+        assert_eq!(BigEndian::read_u32(BigEndian::write_u32(0x1234_5678)), 0x1234_5678);
+    }
+
+    #[test]
+    fn test_endianness_matches_typed_impls() {
+        let value = 0xdead_beefu32;
+        assert_eq!(Endianness::Little.write_u32(value), LittleEndian::write_u32(value));
+        assert_eq!(Endianness::Big.write_u32(value), BigEndian::write_u32(value));
+    }
+}
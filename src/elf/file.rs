@@ -1,9 +1,11 @@
 use std::fs::File;
-use std::io::{Write, Seek, SeekFrom};
+use std::io::{Cursor, Write, Seek, SeekFrom};
+use std::path::Path;
 use crate::utils::Error;
 use crate::elf::format::ElfFormat;
 use crate::elf::header::ElfHeader;
-use crate::elf::section::{ElfSection, Section};
+use crate::elf::section::{DebugRelocator, ElfSection, Section};
+use crate::elf::symbol::Symbol;
 use crate::elf::constants::*;
 
 #[derive(Debug)]
@@ -15,15 +17,20 @@ pub struct ElfFile {
 }
 
 impl ElfFile {
+    /// Read and parse the ELF file at `path`, as [`Self::new`].
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        let data = std::fs::read(path)?;
+        Self::new(&data)
+    }
+
     pub fn new(data: &[u8]) -> Result<Self, Error> {
         // Check ELF magic
         if data.len() < 4 || &data[0..4] != b"\x7fELF" {
             return Err(Error::InvalidFormat("Not an ELF file".into()));
         }
 
-        // Create format and parse header
-        let fmt = ElfFormat::new(data[EI_DATA] == 2); // EI_DATA == 2 means big endian
-        let header = ElfHeader::new(&fmt, &data[0..52])?;
+        let header = ElfHeader::new(data).map_err(|e| Error::InvalidFormat(e.to_string()))?;
+        let fmt = header.fmt;
 
         // Parse sections
         let mut sections = Vec::new();
@@ -33,7 +40,7 @@ impl ElfFile {
         // Parse null section first
         let mut null_section = ElfSection::new(fmt, &data[offset..offset + size])?;
         let num_sections = if header.e_shnum == 0 {
-            null_section.sh_size
+            null_section.sh_size as u32
         } else {
             header.e_shnum as u32
         };
@@ -47,6 +54,7 @@ impl ElfFile {
             let mut section = ElfSection::new(fmt, &data[ind..ind + size])?;
             section.index = i as usize;
             section.init_data(data)?;
+            section.decompress()?;
             sections.push(section);
         }
 
@@ -71,9 +79,13 @@ impl ElfFile {
         };
 
         // Initialize section names and perform late initialization
-        let shstr_idx = file.header.e_shstrndx as usize;
+        let shstr_idx = if file.header.e_shstrndx == SHN_XINDEX {
+            file.sections[0].sh_link as usize
+        } else {
+            file.header.e_shstrndx as usize
+        };
         for i in 0..file.sections.len() {
-            let name = file.sections[shstr_idx].lookup_str(file.sections[i].sh_name as usize)?;
+            let name = file.sections[shstr_idx].lookup_str(file.sections[i].sh_name);
             file.sections[i].name = name;
         }
 
@@ -90,12 +102,12 @@ impl ElfFile {
         self.sections.iter().find(|s| s.name == name)
     }
 
-    pub fn add_section(&mut self, name: &str, sh_type: u32, sh_flags: u32, 
-                      sh_link: u32, sh_info: u32, sh_addralign: u32, 
-                      sh_entsize: u32, data: Vec<u8>) -> Result<usize, Error> {
+    pub fn add_section(&mut self, name: &str, sh_type: u32, sh_flags: u64,
+                      sh_link: u32, sh_info: u32, sh_addralign: u64,
+                      sh_entsize: u64, data: Vec<u8>) -> Result<usize, Error> {
         let shstr = &mut self.sections[self.header.e_shstrndx as usize];
         let sh_name = shstr.add_str(name)?;
-        
+
         let index = self.sections.len();
         let section = ElfSection::from_parts(
             self.fmt,
@@ -113,10 +125,22 @@ impl ElfFile {
         self.sections.push(section);
         let mut sections_clone = self.sections.clone();
         self.sections[index].late_init(&mut sections_clone)?;
-        
+
         Ok(index)
     }
 
+    /// Re-compress the named section with [`ElfSection::compress`], for
+    /// callers that want to shrink specific sections (e.g. `.mdebug`,
+    /// `.debug_*`) before [`Self::write`]. Errors if no section has that
+    /// name.
+    pub fn compress_section(&mut self, name: &str, ch_type: u32) -> Result<(), Error> {
+        self.sections
+            .iter_mut()
+            .find(|s| s.name == name)
+            .ok_or_else(|| Error::InvalidSection(format!("No {} section", name)))?
+            .compress(ch_type)
+    }
+
     pub fn drop_mdebug_gptab(&mut self) {
         while let Some(section) = self.sections.last() {
             if section.sh_type != SHT_MIPS_DEBUG && section.sh_type != SHT_MIPS_GPTAB {
@@ -126,15 +150,79 @@ impl ElfFile {
         }
     }
 
+    /// Emit or refresh the `SHT_SYMTAB_SHNDX` section that complements
+    /// `self.symtab`, if any of its symbols need one (their resolved
+    /// section index no longer fits in `st_shndx`). A no-op otherwise.
+    fn sync_symtab_shndx(&mut self) -> Result<(), Error> {
+        let symtab = self.symtab;
+        let needs_xindex = self.sections[symtab].symbols.iter().any(|s| s.needs_xindex());
+        if !needs_xindex {
+            return Ok(());
+        }
+
+        let mut data = Vec::with_capacity(self.sections[symtab].symbols.len() * 4);
+        for symbol in &self.sections[symtab].symbols {
+            data.extend_from_slice(&self.fmt.pack_u32(symbol.xindex_entry()));
+        }
+
+        if let Some(existing) = self
+            .sections
+            .iter_mut()
+            .find(|s| s.sh_type == SHT_SYMTAB_SHNDX && s.sh_link as usize == symtab)
+        {
+            existing.sh_size = data.len() as u64;
+            existing.data = data;
+            return Ok(());
+        }
+
+        let shstrndx = if self.header.e_shstrndx == SHN_XINDEX {
+            self.sections[0].sh_link as usize
+        } else {
+            self.header.e_shstrndx as usize
+        };
+        let sh_name = self.sections[shstrndx].add_str(".symtab_shndx")?;
+        let index = self.sections.len();
+        let section = ElfSection::from_parts(self.fmt, sh_name, SHT_SYMTAB_SHNDX, 0, symtab as u32, 0, 4, 4, data, index);
+        self.sections.push(section);
+        Ok(())
+    }
+
     pub fn write(&mut self, filename: &str) -> Result<(), Error> {
         let mut file = File::create(filename)?;
-        let mut outidx: u32 = 0;
+        self.write_to(&mut file)
+    }
 
-        // Write header
-        self.header.e_shnum = self.sections.len() as u16;
-        let header_bytes = self.header.to_bytes(&self.fmt)?;
+    /// As [`Self::write`], but serializes to an in-memory buffer instead of
+    /// a path on disk, for callers embedding this crate as a library that
+    /// keep build artifacts in memory rather than on the filesystem.
+    pub fn write_to_vec(&mut self) -> Result<Vec<u8>, Error> {
+        let mut cursor = Cursor::new(Vec::new());
+        self.write_to(&mut cursor)?;
+        Ok(cursor.into_inner())
+    }
+
+    fn write_to<W: Write + Seek>(&mut self, writer: &mut W) -> Result<(), Error> {
+        self.sync_symtab_shndx()?;
+
+        let file = writer;
+        let mut outidx: u64 = 0;
+
+        // Write header, applying SHN_XINDEX overflow for e_shnum/e_shstrndx
+        // when there are too many sections (or the string table index is
+        // too big) to fit in their normal fields.
+        if self.sections.len() >= SHN_LORESERVE as usize {
+            self.header.e_shnum = 0;
+            self.sections[0].sh_size = self.sections.len() as u64;
+        } else {
+            self.header.e_shnum = self.sections.len() as u16;
+        }
+        if self.header.e_shstrndx != SHN_XINDEX && self.header.e_shstrndx as usize >= SHN_LORESERVE as usize {
+            self.sections[0].sh_link = self.header.e_shstrndx as u32;
+            self.header.e_shstrndx = SHN_XINDEX;
+        }
+        let header_bytes = self.header.to_bytes();
         file.write_all(&header_bytes)?;
-        outidx += header_bytes.len() as u32;
+        outidx += header_bytes.len() as u64;
 
         // Write section data
         for section in self.sections.iter_mut() {
@@ -144,18 +232,18 @@ impl ElfFile {
                     let padding = section.sh_addralign - (outidx % section.sh_addralign);
                     let padding_bytes = vec![0; padding as usize];
                     file.write_all(&padding_bytes)?;
-                    outidx += padding as u32;
+                    outidx += padding;
                 }
 
                 let old_offset = section.sh_offset;
                 section.sh_offset = outidx;
-                
-                if section.sh_type == SHT_MIPS_REGINFO && section.sh_offset != old_offset {
-                    section.relocate_mdebug(old_offset)?;
+
+                if section.sh_offset != old_offset {
+                    section.relocate_debug(old_offset)?;
                 }
-                
+
                 file.write_all(&section.data)?;
-                outidx += section.data.len() as u32;
+                outidx += section.data.len() as u64;
             }
         }
 
@@ -172,12 +260,12 @@ impl ElfFile {
         for section in &self.sections {
             let section_bytes = section.to_bytes();
             file.write_all(&section_bytes)?;
-            outidx += section_bytes.len() as u32;
+            outidx += section_bytes.len() as u64;
         }
 
         // Update header with new section header offset
         file.seek(SeekFrom::Start(0))?;
-        let header_bytes = self.header.to_bytes(&self.fmt)?;
+        let header_bytes = self.header.to_bytes();
         file.write_all(&header_bytes)?;
 
         Ok(())
@@ -194,7 +282,7 @@ mod tests {
         // Create test data
         let fmt = ElfFormat::new(true); // Big endian
         let mut data = vec![0; 0x1000];
-        
+
         // ELF magic and identification
         data[0..4].copy_from_slice(b"\x7fELF");
         data[EI_CLASS] = 1; // 32-bit
@@ -203,71 +291,60 @@ mod tests {
         data[EI_OSABI] = 0; // OS ABI
         data[EI_ABIVERSION] = 0; // ABI Version
 
-        fmt.pack_u16(&mut data[16..18], 1)?; 
-        fmt.pack_u16(&mut data[18..20], 8)?; // EM_MIPS
+        data[16..18].copy_from_slice(&fmt.pack_u16(1)); // e_type
+        data[18..20].copy_from_slice(&fmt.pack_u16(8)); // EM_MIPS
 
         // Set these values in the raw data
-        fmt.pack_u32(&mut data[24..28], 1)?; // e_version
-        fmt.pack_u16(&mut data[46..48], 40)?; // e_shentsize
-        fmt.pack_u16(&mut data[48..50], 3)?; // e_shnum - now 3 sections
-        
+        data[24..28].copy_from_slice(&fmt.pack_u32(1)); // e_version
+        data[46..48].copy_from_slice(&fmt.pack_u16(40)); // e_shentsize
+        data[48..50].copy_from_slice(&fmt.pack_u16(3)); // e_shnum - now 3 sections
+
         // Create sections data
         let strtab_offset = 0x200;
         let symtab_offset = 0x300;
-        
+
         // Create string table data
         let strtab_data = b"\0.strtab\0.symtab\0.test\0";
-        println!("String table data: {:?}", strtab_data);
         data[strtab_offset..strtab_offset + strtab_data.len()].copy_from_slice(strtab_data);
-        
+
         // Create symbol table data (just a null symbol)
         let symtab_data = vec![0; 16];
         data[symtab_offset..symtab_offset + symtab_data.len()].copy_from_slice(&symtab_data);
-        
+
         // Create section headers at offset 0x100
         let sh_offset = 0x100;
-        
+
         // Null section
-        let mut null_section = ElfSection::default();
-        null_section.sh_name = 0;
+        let null_section = ElfSection::default();
         data[sh_offset..sh_offset + 40].copy_from_slice(&null_section.to_bytes());
-        
+
         // String table section
         let mut strtab = ElfSection::default();
         strtab.sh_type = SHT_STRTAB;
-        strtab.sh_offset = strtab_offset as u32;
-        strtab.sh_size = strtab_data.len() as u32;
+        strtab.sh_offset = strtab_offset as u64;
+        strtab.sh_size = strtab_data.len() as u64;
         strtab.sh_name = 1; // Points to ".strtab" in the string table
         strtab.data = strtab_data.to_vec();
-        println!("String table section data: {:?}", strtab.data);
         data[sh_offset + 40..sh_offset + 80].copy_from_slice(&strtab.to_bytes());
-        
+
         // Symbol table section
         let mut symtab = ElfSection::default();
         symtab.sh_type = SHT_SYMTAB;
         symtab.sh_link = 1; // Link to string table
-        symtab.sh_offset = symtab_offset as u32;
-        symtab.sh_size = symtab_data.len() as u32;
+        symtab.sh_offset = symtab_offset as u64;
+        symtab.sh_size = symtab_data.len() as u64;
         symtab.sh_entsize = 16;
         symtab.sh_name = 8; // Points to ".symtab" in the string table
         symtab.data = symtab_data.clone();
         data[sh_offset + 80..sh_offset + 120].copy_from_slice(&symtab.to_bytes());
-        
+
         // Set section header offset in ELF header
-        fmt.pack_u32(&mut data[32..36], sh_offset as u32)?; // e_shoff
-        fmt.pack_u16(&mut data[50..52], 1)?; // e_shstrndx - points to strtab
-        
+        data[32..36].copy_from_slice(&fmt.pack_u32(sh_offset as u32)); // e_shoff
+        data[50..52].copy_from_slice(&fmt.pack_u16(1)); // e_shstrndx - points to strtab
+
         // Create ELF file from test data
         let mut elf = ElfFile::new(&data)?;
-        println!("Created ELF file with {} sections", elf.sections.len());
-        for (i, section) in elf.sections.iter().enumerate() {
-            println!("Section {}: type={}, offset={}, size={}, data.len()={}", 
-                    i, section.sh_type, section.sh_offset, section.sh_size, section.data.len());
-            if section.sh_type == SHT_STRTAB {
-                println!("String table data after init: {:?}", section.data);
-            }
-        }
-        
+
         // Add a new section
         let new_section_idx = elf.add_section(
             ".test2",
@@ -279,21 +356,81 @@ mod tests {
             0,
             vec![1, 2, 3, 4]
         )?;
-        
+
         // Write to temporary file
         let temp_file = "test_elf.tmp";
         elf.write(temp_file)?;
-        
+
         // Read back and verify
         let data = fs::read(temp_file)?;
         let elf2 = ElfFile::new(&data)?;
-        
+
         assert_eq!(elf2.sections.len(), elf.sections.len());
         assert_eq!(elf2.sections[new_section_idx].data, vec![1, 2, 3, 4]);
-        
+
         // Clean up
         fs::remove_file(temp_file)?;
-        
+
+        Ok(())
+    }
+
+    /// A symbol whose resolved section index no longer fits in `st_shndx`
+    /// (simulating the result of a builder remap past a large section list)
+    /// should come back out through `SHN_XINDEX` plus a `SHT_SYMTAB_SHNDX`
+    /// section, round-tripping to the same resolved index.
+    #[test]
+    fn test_elf_file_write_emits_symtab_shndx() -> Result<(), Error> {
+        let fmt = ElfFormat::new(true);
+
+        let null_section = ElfSection::default();
+        let shstrtab = ElfSection {
+            fmt,
+            sh_type: SHT_STRTAB,
+            data: b"\0.shstrtab\0".to_vec(),
+            ..ElfSection::default()
+        };
+        let mut symtab_section = ElfSection {
+            fmt,
+            sh_type: SHT_SYMTAB,
+            sh_link: 1,
+            sh_entsize: 16,
+            ..ElfSection::default()
+        };
+        let mut sym = Symbol::from_parts(fmt, 0, 0, 0, 0, 0, 1, &shstrtab, "big_sym".to_string())
+            .map_err(|e| Error::InvalidSymbol(e.to_string()))?;
+        sym.set_shndx(0x1_0002);
+        symtab_section.symbols.push(sym);
+        symtab_section.data = symtab_section.symbols.iter().flat_map(|s| s.to_bytes()).collect();
+
+        let mut h = vec![0u8; 52];
+        h[0..4].copy_from_slice(b"\x7fELF");
+        h[EI_CLASS] = 1;
+        h[EI_DATA] = 2;
+        h[EI_VERSION] = 1;
+        h[16..18].copy_from_slice(&fmt.pack_u16(1));
+        h[18..20].copy_from_slice(&fmt.pack_u16(8));
+        h[32..36].copy_from_slice(&fmt.pack_u32(1));
+        h[46..48].copy_from_slice(&fmt.pack_u16(40));
+        h[50..52].copy_from_slice(&fmt.pack_u16(1));
+        let header = ElfHeader::new(&h).map_err(|e| Error::InvalidFormat(e.to_string()))?;
+
+        let mut elf = ElfFile {
+            header,
+            sections: vec![null_section, shstrtab, symtab_section],
+            fmt,
+            symtab: 2,
+        };
+
+        let temp_file = "test_elf_file_symtab_shndx.tmp";
+        elf.write(temp_file)?;
+
+        let data = fs::read(temp_file)?;
+        let written = ElfFile::new(&data)?;
+        fs::remove_file(temp_file)?;
+
+        assert!(written.sections.iter().any(|s| s.sh_type == SHT_SYMTAB_SHNDX));
+        assert_eq!(written.sections[written.symtab].symbols[0].shndx32, 0x1_0002);
+
         Ok(())
     }
 }
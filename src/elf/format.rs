@@ -1,112 +1,175 @@
+use crate::elf::constants::{EI_CLASS, EI_DATA, EI_NIDENT, ELFCLASS32, ELFCLASS64, ELFDATA2LSB, ELFDATA2MSB};
+use crate::elf::endian::Endianness;
 use crate::utils::Error;
-use byteorder::{BigEndian, ByteOrder, LittleEndian};
 
+/// Runtime-dispatched ELF class/endianness descriptor, used by every parser
+/// in this crate that learns its byte order from an object's own `e_ident`
+/// rather than knowing it at compile time. Internally this is just a thin
+/// wrapper over [`Endianness`] plus the 32/64-bit class flag — see
+/// [`crate::elf::endian`] for the zero-cost typed counterparts of
+/// [`Endianness::Little`]/[`Endianness::Big`] ([`LittleEndian`](crate::elf::endian::LittleEndian)/
+/// [`BigEndian`](crate::elf::endian::BigEndian)) for hot paths that already
+/// know the byte order at the type level.
 #[derive(Debug, Clone, Copy)]
 pub struct ElfFormat {
-    big_endian: bool,
+    endianness: Endianness,
+    is_64: bool,
 }
 
 impl ElfFormat {
     pub fn new(big_endian: bool) -> Self {
-        Self { big_endian }
+        Self { endianness: if big_endian { Endianness::Big } else { Endianness::Little }, is_64: false }
+    }
+
+    /// Construct a format descriptor for a 64-bit (`ELFCLASS64`) object.
+    pub fn new_64(big_endian: bool) -> Self {
+        Self { endianness: if big_endian { Endianness::Big } else { Endianness::Little }, is_64: true }
     }
 
     pub fn default() -> Self {
-        Self { big_endian: true }
+        Self { endianness: Endianness::Big, is_64: false }
     }
 
-    pub fn pack_u16(&self, value: u16) -> [u8; 2] {
-        let mut buf = [0; 2];
-        if self.big_endian {
-            BigEndian::write_u16(&mut buf, value);
-        } else {
-            LittleEndian::write_u16(&mut buf, value);
+    /// Derive the class/endianness to parse an object with from its raw
+    /// `e_ident` bytes, instead of assuming [`Self::default`]'s big-endian
+    /// 32-bit layout. Validates the `\x7FELF` magic and rejects
+    /// `ELFCLASSNONE`/`ELFDATANONE` (and any other value outside the
+    /// defined `ELFCLASS32`/`64` and `ELFDATA2LSB`/`2MSB`).
+    pub fn from_ident(e_ident: &[u8]) -> Result<Self, Error> {
+        if e_ident.len() < EI_NIDENT {
+            return Err(Error::InvalidFormat("e_ident too short".into()));
         }
-        buf
+        if &e_ident[0..4] != b"\x7fELF" {
+            return Err(Error::InvalidFormat("not an ELF file (bad magic)".into()));
+        }
+
+        let is_64 = match e_ident[EI_CLASS] {
+            c if c == ELFCLASS32 => false,
+            c if c == ELFCLASS64 => true,
+            c => return Err(Error::InvalidFormat(format!("invalid ELF class {}", c))),
+        };
+
+        let big_endian = match e_ident[EI_DATA] {
+            d if d == ELFDATA2LSB => false,
+            d if d == ELFDATA2MSB => true,
+            d => return Err(Error::InvalidFormat(format!("invalid ELF data encoding {}", d))),
+        };
+
+        Ok(if is_64 { Self::new_64(big_endian) } else { Self::new(big_endian) })
+    }
+
+    pub fn is_64(&self) -> bool {
+        self.is_64
+    }
+
+    pub fn pack_u16(&self, value: u16) -> [u8; 2] {
+        self.endianness.write_u16(value)
     }
 
     pub fn pack_u32(&self, value: u32) -> [u8; 4] {
-        let mut buf = [0; 4];
-        if self.big_endian {
-            BigEndian::write_u32(&mut buf, value);
-        } else {
-            LittleEndian::write_u32(&mut buf, value);
-        }
-        buf
+        self.endianness.write_u32(value)
     }
 
     pub fn unpack_u16(&self, data: &[u8]) -> Result<u16, Error> {
         if data.len() < 2 {
             return Err(Error::InvalidFormat("Data too short for u16".into()));
         }
-        Ok(if self.big_endian {
-            BigEndian::read_u16(data)
-        } else {
-            LittleEndian::read_u16(data)
-        })
+        Ok(self.endianness.read_u16([data[0], data[1]]))
     }
 
     pub fn unpack_u32(&self, data: &[u8]) -> Result<u32, Error> {
         if data.len() < 4 {
             return Err(Error::InvalidFormat("Data too short for u32".into()));
         }
-        Ok(if self.big_endian {
-            BigEndian::read_u32(data)
-        } else {
-            LittleEndian::read_u32(data)
-        })
+        Ok(self.endianness.read_u32([data[0], data[1], data[2], data[3]]))
     }
 
-    pub fn unpack_tuple_u32(&self, data: &[u8]) -> Result<(u32, u32), Error> {
+    pub fn pack_u64(&self, value: u64) -> [u8; 8] {
+        self.endianness.write_u64(value)
+    }
+
+    pub fn unpack_u64(&self, data: &[u8]) -> Result<u64, Error> {
         if data.len() < 8 {
-            return Err(Error::InvalidFormat("Data too short for u32 tuple".into()));
+            return Err(Error::InvalidFormat("Data too short for u64".into()));
         }
-        Ok((
-            self.unpack_u32(&data[0..4])?,
-            self.unpack_u32(&data[4..8])?,
-        ))
+        Ok(self.endianness.read_u64([data[0], data[1], data[2], data[3], data[4], data[5], data[6], data[7]]))
+    }
+
+    pub fn unpack_tuple_u32(&self, data: &[u8]) -> Result<(u32, u32), Error> {
+        let mut r = self.reader(data);
+        Ok((r.u32()?, r.u32()?))
     }
 
     pub fn unpack_tuple_u32_3(&self, data: &[u8]) -> Result<(u32, u32, u32), Error> {
-        if data.len() < 12 {
-            return Err(Error::InvalidFormat("Data too short for u32 tuple".into()));
-        }
-        Ok((
-            self.unpack_u32(&data[0..4])?,
-            self.unpack_u32(&data[4..8])?,
-            self.unpack_u32(&data[8..12])?,
-        ))
+        let mut r = self.reader(data);
+        Ok((r.u32()?, r.u32()?, r.u32()?))
     }
 
     pub fn unpack_symbol(&self, data: &[u8]) -> Result<(u32, u32, u32, u8, u8, u16), Error> {
-        if data.len() < 16 {
-            return Err(Error::InvalidFormat("Data too short for symbol".into()));
-        }
+        let mut r = self.reader(data);
         Ok((
-            self.unpack_u32(&data[0..4])?,   // st_name
-            self.unpack_u32(&data[4..8])?,   // st_value
-            self.unpack_u32(&data[8..12])?,  // st_size
-            data[12],                       // st_info
-            data[13],                       // st_other
-            self.unpack_u16(&data[14..16])?, // st_shndx
+            r.u32()?, // st_name
+            r.u32()?, // st_value
+            r.u32()?, // st_size
+            r.u8()?,  // st_info
+            r.u8()?,  // st_other
+            r.u16()?, // st_shndx
         ))
     }
 
-    pub fn unpack_tuple_u32_10(&self, data: &[u8]) -> Result<(u32, u32, u32, u32, u32, u32, u32, u32, u32, u32), Error> {
-        if data.len() < 40 {
-            return Err(Error::InvalidFormat("Data too short for u32 tuple".into()));
+    /// Size in bytes of a symbol table entry for this format (16 for
+    /// `Elf32_Sym`, 24 for `Elf64_Sym`).
+    pub fn symbol_entry_size(&self) -> usize {
+        if self.is_64 { 24 } else { 16 }
+    }
+
+    /// Unpack a symbol table entry, dispatching on the 32/64-bit layout.
+    /// `Elf32_Sym` is `st_name, st_value, st_size, st_info, st_other,
+    /// st_shndx` (16 bytes); `Elf64_Sym` reorders to `st_name, st_info,
+    /// st_other, st_shndx, st_value, st_size` (24 bytes).
+    pub fn unpack_symbol64(&self, data: &[u8]) -> Result<(u32, u64, u64, u8, u8, u16), Error> {
+        if self.is_64 {
+            let mut r = self.reader(data);
+            let st_name = r.u32()?;
+            let st_info = r.u8()?;
+            let st_other = r.u8()?;
+            let st_shndx = r.u16()?;
+            let st_value = r.u64()?;
+            let st_size = r.u64()?;
+            Ok((st_name, st_value, st_size, st_info, st_other, st_shndx))
+        } else {
+            let (st_name, st_value, st_size, st_info, st_other, st_shndx) = self.unpack_symbol(data)?;
+            Ok((st_name, st_value as u64, st_size as u64, st_info, st_other, st_shndx))
+        }
+    }
+
+    /// Pack a symbol table entry, dispatching on the 32/64-bit layout. See
+    /// [`Self::unpack_symbol64`] for the field orderings.
+    pub fn pack_symbol64(&self, st_name: u32, st_value: u64, st_size: u64, st_info: u8, st_other: u8, st_shndx: u16) -> Vec<u8> {
+        let mut w = self.writer();
+        if self.is_64 {
+            w.push_u32(st_name);
+            w.push_u8(st_info);
+            w.push_u8(st_other);
+            w.push_u16(st_shndx);
+            w.push_u64(st_value);
+            w.push_u64(st_size);
+        } else {
+            w.push_u32(st_name);
+            w.push_u32(st_value as u32);
+            w.push_u32(st_size as u32);
+            w.push_u8(st_info);
+            w.push_u8(st_other);
+            w.push_u16(st_shndx);
         }
+        w.into_bytes()
+    }
+
+    pub fn unpack_tuple_u32_10(&self, data: &[u8]) -> Result<(u32, u32, u32, u32, u32, u32, u32, u32, u32, u32), Error> {
+        let mut r = self.reader(data);
         Ok((
-            self.unpack_u32(&data[0..4])?,
-            self.unpack_u32(&data[4..8])?,
-            self.unpack_u32(&data[8..12])?,
-            self.unpack_u32(&data[12..16])?,
-            self.unpack_u32(&data[16..20])?,
-            self.unpack_u32(&data[20..24])?,
-            self.unpack_u32(&data[24..28])?,
-            self.unpack_u32(&data[28..32])?,
-            self.unpack_u32(&data[32..36])?,
-            self.unpack_u32(&data[36..40])?,
+            r.u32()?, r.u32()?, r.u32()?, r.u32()?, r.u32()?,
+            r.u32()?, r.u32()?, r.u32()?, r.u32()?, r.u32()?,
         ))
     }
 
@@ -123,61 +186,150 @@ impl ElfFormat {
         v9: u32,
         v10: u32,
     ) -> Vec<u8> {
-        let mut result = Vec::with_capacity(40);
-        result.extend_from_slice(&self.pack_u32(v1));
-        result.extend_from_slice(&self.pack_u32(v2));
-        result.extend_from_slice(&self.pack_u32(v3));
-        result.extend_from_slice(&self.pack_u32(v4));
-        result.extend_from_slice(&self.pack_u32(v5));
-        result.extend_from_slice(&self.pack_u32(v6));
-        result.extend_from_slice(&self.pack_u32(v7));
-        result.extend_from_slice(&self.pack_u32(v8));
-        result.extend_from_slice(&self.pack_u32(v9));
-        result.extend_from_slice(&self.pack_u32(v10));
-        result
+        let mut w = self.writer();
+        for v in [v1, v2, v3, v4, v5, v6, v7, v8, v9, v10] {
+            w.push_u32(v);
+        }
+        w.into_bytes()
     }
 
     pub fn unpack_tuple_u32_25(&self, data: &[u8]) -> Result<(u32, u32, u32, u32, u32, u32, u32, u32, u32, u32, u32, u32, u32, u32, u32, u32, u32, u32, u32, u32, u32, u32, u32, u32, u32), Error> {
-        if data.len() < 100 {
-            return Err(Error::InvalidFormat("Data too short for u32 tuple".into()));
-        }
+        let mut r = self.reader(data);
         Ok((
-            self.unpack_u32(&data[0..4])?,
-            self.unpack_u32(&data[4..8])?,
-            self.unpack_u32(&data[8..12])?,
-            self.unpack_u32(&data[12..16])?,
-            self.unpack_u32(&data[16..20])?,
-            self.unpack_u32(&data[20..24])?,
-            self.unpack_u32(&data[24..28])?,
-            self.unpack_u32(&data[28..32])?,
-            self.unpack_u32(&data[32..36])?,
-            self.unpack_u32(&data[36..40])?,
-            self.unpack_u32(&data[40..44])?,
-            self.unpack_u32(&data[44..48])?,
-            self.unpack_u32(&data[48..52])?,
-            self.unpack_u32(&data[52..56])?,
-            self.unpack_u32(&data[56..60])?,
-            self.unpack_u32(&data[60..64])?,
-            self.unpack_u32(&data[64..68])?,
-            self.unpack_u32(&data[68..72])?,
-            self.unpack_u32(&data[72..76])?,
-            self.unpack_u32(&data[76..80])?,
-            self.unpack_u32(&data[80..84])?,
-            self.unpack_u32(&data[84..88])?,
-            self.unpack_u32(&data[88..92])?,
-            self.unpack_u32(&data[92..96])?,
-            self.unpack_u32(&data[96..100])?,
+            r.u32()?, r.u32()?, r.u32()?, r.u32()?, r.u32()?,
+            r.u32()?, r.u32()?, r.u32()?, r.u32()?, r.u32()?,
+            r.u32()?, r.u32()?, r.u32()?, r.u32()?, r.u32()?,
+            r.u32()?, r.u32()?, r.u32()?, r.u32()?, r.u32()?,
+            r.u32()?, r.u32()?, r.u32()?, r.u32()?, r.u32()?,
         ))
     }
 
     pub fn pack_symbol(&self, st_name: u32, st_value: u32, st_size: u32, info_other: u16, st_shndx: u16) -> Vec<u8> {
-        let mut result = Vec::with_capacity(16);
-        result.extend_from_slice(&self.pack_u32(st_name));
-        result.extend_from_slice(&self.pack_u32(st_value));
-        result.extend_from_slice(&self.pack_u32(st_size));
-        result.extend_from_slice(&self.pack_u16(info_other));
-        result.extend_from_slice(&self.pack_u16(st_shndx));
-        result
+        let mut w = self.writer();
+        w.push_u32(st_name);
+        w.push_u32(st_value);
+        w.push_u32(st_size);
+        w.push_u16(info_other);
+        w.push_u16(st_shndx);
+        w.into_bytes()
+    }
+
+    /// A sequential bounds-checked cursor over `data`, in this format's
+    /// endianness. See [`Reader`].
+    pub fn reader<'a>(&self, data: &'a [u8]) -> Reader<'a> {
+        Reader::new(*self, data)
+    }
+
+    /// A sequential byte accumulator, packing in this format's endianness.
+    /// See [`Writer`].
+    pub fn writer(&self) -> Writer {
+        Writer::new(*self)
+    }
+}
+
+/// A sequential, bounds-checked cursor over a byte slice, read in `fmt`'s
+/// endianness. Each accessor advances the internal offset and errors with
+/// the failing offset and requested length on a short read, replacing the
+/// ad hoc `&data[a..b]` slicing the typed `unpack_*` helpers used to do by
+/// hand.
+pub struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+    fmt: ElfFormat,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(fmt: ElfFormat, data: &'a [u8]) -> Self {
+        Self { data, offset: 0, fmt }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.data.len() - self.offset
+    }
+
+    pub fn bytes(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if n > self.remaining() {
+            return Err(Error::InvalidFormat(format!(
+                "tried to read {} bytes at offset {} but only {} remain",
+                n, self.offset, self.remaining()
+            )));
+        }
+        let slice = &self.data[self.offset..self.offset + n];
+        self.offset += n;
+        Ok(slice)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn u16(&mut self) -> Result<u16, Error> {
+        self.fmt.unpack_u16(self.bytes(2)?)
+    }
+
+    pub fn u32(&mut self) -> Result<u32, Error> {
+        self.fmt.unpack_u32(self.bytes(4)?)
+    }
+
+    pub fn u64(&mut self) -> Result<u64, Error> {
+        self.fmt.unpack_u64(self.bytes(8)?)
+    }
+
+    pub fn opt_u8(&mut self) -> Option<u8> {
+        self.u8().ok()
+    }
+
+    pub fn opt_u16(&mut self) -> Option<u16> {
+        self.u16().ok()
+    }
+
+    pub fn opt_u32(&mut self) -> Option<u32> {
+        self.u32().ok()
+    }
+
+    pub fn opt_u64(&mut self) -> Option<u64> {
+        self.u64().ok()
+    }
+}
+
+/// A sequential byte accumulator, the write-side counterpart to [`Reader`]:
+/// each `push_*` packs a value in `fmt`'s endianness and appends it.
+pub struct Writer {
+    fmt: ElfFormat,
+    data: Vec<u8>,
+}
+
+impl Writer {
+    pub fn new(fmt: ElfFormat) -> Self {
+        Self { fmt, data: Vec::new() }
+    }
+
+    pub fn push_u8(&mut self, value: u8) {
+        self.data.push(value);
+    }
+
+    pub fn push_u16(&mut self, value: u16) {
+        self.data.extend_from_slice(&self.fmt.pack_u16(value));
+    }
+
+    pub fn push_u32(&mut self, value: u32) {
+        self.data.extend_from_slice(&self.fmt.pack_u32(value));
+    }
+
+    pub fn push_u64(&mut self, value: u64) {
+        self.data.extend_from_slice(&self.fmt.pack_u64(value));
+    }
+
+    pub fn push_bytes(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
     }
 }
 
@@ -264,4 +416,64 @@ mod tests {
         assert_eq!(st_other, 0x34);
         assert_eq!(st_shndx, 0x5678);
     }
+
+    #[test]
+    fn test_pack_unpack_u64() {
+        let fmt = ElfFormat::new(true);
+        let value = 0x0123456789abcdef;
+        let packed = fmt.pack_u64(value);
+        let unpacked = fmt.unpack_u64(&packed).unwrap();
+        assert_eq!(value, unpacked);
+    }
+
+    #[test]
+    fn test_symbol64_roundtrip() {
+        let fmt = ElfFormat::new_64(true);
+        let bytes = fmt.pack_symbol64(1, 0x1_0000_0000, 0x20, 0x12, 0x3, 5);
+        assert_eq!(bytes.len(), 24);
+        let (st_name, st_value, st_size, st_info, st_other, st_shndx) = fmt.unpack_symbol64(&bytes).unwrap();
+        assert_eq!(st_name, 1);
+        assert_eq!(st_value, 0x1_0000_0000);
+        assert_eq!(st_size, 0x20);
+        assert_eq!(st_info, 0x12);
+        assert_eq!(st_other, 0x3);
+        assert_eq!(st_shndx, 5);
+    }
+
+    #[test]
+    fn test_symbol32_via_symbol64_path() {
+        let fmt = ElfFormat::new(true);
+        let bytes = fmt.pack_symbol64(1, 0x1000, 0x20, 0x12, 0x3, 5);
+        assert_eq!(bytes.len(), 16);
+        let (.., st_value, st_size, ..) = fmt.unpack_symbol64(&bytes).unwrap();
+        assert_eq!(st_value, 0x1000);
+        assert_eq!(st_size, 0x20);
+    }
+
+    #[test]
+    fn test_reader_sequential() {
+        let fmt = ElfFormat::new(true);
+        let mut w = fmt.writer();
+        w.push_u8(0x12);
+        w.push_u16(0x3456);
+        w.push_u32(0x789abcde);
+        w.push_u64(0x0011223344556677);
+        let data = w.into_bytes();
+
+        let mut r = fmt.reader(&data);
+        assert_eq!(r.u8().unwrap(), 0x12);
+        assert_eq!(r.u16().unwrap(), 0x3456);
+        assert_eq!(r.u32().unwrap(), 0x789abcde);
+        assert_eq!(r.u64().unwrap(), 0x0011223344556677);
+        assert_eq!(r.remaining(), 0);
+    }
+
+    #[test]
+    fn test_reader_short_read_errors() {
+        let fmt = ElfFormat::new(true);
+        let data = [0u8; 2];
+        let mut r = fmt.reader(&data);
+        assert!(r.u32().is_err());
+        assert_eq!(r.opt_u32(), None);
+    }
 }
@@ -4,8 +4,14 @@ use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum ElfHeaderError {
-    #[error("Invalid ELF class (expected 32-bit)")]
+    #[error("Invalid ELF class (expected 32-bit or 64-bit)")]
     InvalidClass,
+    #[error("Invalid ELF identification: {0}")]
+    InvalidIdent(String),
+    #[error("Not an ELF file (bad magic bytes)")]
+    InvalidMagic,
+    #[error("Invalid ELF identification version (expected EV_CURRENT)")]
+    InvalidIdentVersion,
     #[error("Invalid ELF type (expected relocatable)")]
     InvalidType,
     #[error("Invalid machine type (expected MIPS I)")]
@@ -18,15 +24,21 @@ pub enum ElfHeaderError {
     InvalidSectionStringTableIndex,
 }
 
+/// An ELF file header (`Elf32_Ehdr`/`Elf64_Ehdr`).
+///
+/// `e_entry`/`e_phoff`/`e_shoff` are stored widened to `u64` so the same
+/// struct covers both the 32-bit (52-byte) and 64-bit (64-byte) layouts;
+/// [`ElfHeader::fmt`]'s [`ElfFormat::is_64`] records which one this header
+/// was parsed from, since everything else is identical between the two.
 #[derive(Debug, Clone)]
 pub struct ElfHeader {
     pub e_ident: [u8; EI_NIDENT],
     pub e_type: u16,
     pub e_machine: u16,
     pub e_version: u32,
-    pub e_entry: u32,
-    pub e_phoff: u32,
-    pub e_shoff: u32,
+    pub e_entry: u64,
+    pub e_phoff: u64,
+    pub e_shoff: u64,
     pub e_flags: u32,
     pub e_ehsize: u16,
     pub e_phentsize: u16,
@@ -38,41 +50,87 @@ pub struct ElfHeader {
 }
 
 impl ElfHeader {
+    /// Size of the fixed fields following `e_ident` (`e_type`..`e_shstrndx`).
+    fn rest_size(is_64: bool) -> usize {
+        if is_64 { 48 } else { 36 }
+    }
+
+    /// Parse a header, requiring `ET_REL`/`EM_MIPS` as the processing
+    /// pipeline does. Use [`Self::parse_lenient`] to read headers of
+    /// arbitrary ELF objects (executables, shared objects, other
+    /// architectures) for read-only inspection instead.
     pub fn new(data: &[u8]) -> Result<Self, ElfHeaderError> {
+        Self::parse(data, true)
+    }
+
+    /// Parse a header without asserting the MIPS relocatable-object shape
+    /// (`e_type == ET_REL`, `e_machine == EM_MIPS`, `e_phoff == 0`), so
+    /// tooling can dump headers of executables, shared objects, or other
+    /// architectures. Structural fields (magic, ident version, class,
+    /// non-zero `e_shoff`, valid `e_shstrndx`) are still validated;
+    /// `e_type`/`e_machine` are recorded as-is on the returned header
+    /// rather than rejected.
+    pub fn parse_lenient(data: &[u8]) -> Result<Self, ElfHeaderError> {
+        Self::parse(data, false)
+    }
+
+    fn parse(data: &[u8], strict: bool) -> Result<Self, ElfHeaderError> {
+        if data.len() < EI_NIDENT {
+            return Err(ElfHeaderError::InvalidIdent("e_ident too short".into()));
+        }
         let mut e_ident = [0u8; EI_NIDENT];
         e_ident.copy_from_slice(&data[..EI_NIDENT]);
-        
-        // Verify 32-bit class
-        if e_ident[EI_CLASS] != 1 {
-            return Err(ElfHeaderError::InvalidClass);
+
+        if e_ident[0..4] != [0x7f, b'E', b'L', b'F'] {
+            return Err(ElfHeaderError::InvalidMagic);
+        }
+        if e_ident[EI_VERSION] != 1 {
+            return Err(ElfHeaderError::InvalidIdentVersion);
         }
 
-        let fmt = ElfFormat::new(e_ident[EI_DATA] == 2);
-        
+        let fmt = ElfFormat::from_ident(&e_ident).map_err(|e| ElfHeaderError::InvalidIdent(e.to_string()))?;
+        let is_64 = fmt.is_64();
+
         // Parse the remaining fields
-        let e_type = fmt.unpack_u16(&data[EI_NIDENT..EI_NIDENT + 2]);
-        let e_machine = fmt.unpack_u16(&data[EI_NIDENT + 2..EI_NIDENT + 4]);
-        let e_version = fmt.unpack_u32(&data[EI_NIDENT + 4..EI_NIDENT + 8]);
-        let e_entry = fmt.unpack_u32(&data[EI_NIDENT + 8..EI_NIDENT + 12]);
-        let e_phoff = fmt.unpack_u32(&data[EI_NIDENT + 12..EI_NIDENT + 16]);
-        let e_shoff = fmt.unpack_u32(&data[EI_NIDENT + 16..EI_NIDENT + 20]);
-        let e_flags = fmt.unpack_u32(&data[EI_NIDENT + 20..EI_NIDENT + 24]);
-        let e_ehsize = fmt.unpack_u16(&data[EI_NIDENT + 24..EI_NIDENT + 26]);
-        let e_phentsize = fmt.unpack_u16(&data[EI_NIDENT + 26..EI_NIDENT + 28]);
-        let e_phnum = fmt.unpack_u16(&data[EI_NIDENT + 28..EI_NIDENT + 30]);
-        let e_shentsize = fmt.unpack_u16(&data[EI_NIDENT + 30..EI_NIDENT + 32]);
-        let e_shnum = fmt.unpack_u16(&data[EI_NIDENT + 32..EI_NIDENT + 34]);
-        let e_shstrndx = fmt.unpack_u16(&data[EI_NIDENT + 34..EI_NIDENT + 36]);
-
-        // Validate fields
-        if e_type != 1 {
-            return Err(ElfHeaderError::InvalidType);
-        }
-        if e_machine != 8 {
-            return Err(ElfHeaderError::InvalidMachine);
+        let e_type = fmt.unpack_u16(&data[EI_NIDENT..EI_NIDENT + 2]).map_err(|_| ElfHeaderError::InvalidClass)?;
+        let e_machine = fmt.unpack_u16(&data[EI_NIDENT + 2..EI_NIDENT + 4]).map_err(|_| ElfHeaderError::InvalidClass)?;
+        let e_version = fmt.unpack_u32(&data[EI_NIDENT + 4..EI_NIDENT + 8]).map_err(|_| ElfHeaderError::InvalidClass)?;
+
+        let (e_entry, e_phoff, e_shoff, rest);
+        if is_64 {
+            e_entry = fmt.unpack_u64(&data[EI_NIDENT + 8..EI_NIDENT + 16]).map_err(|_| ElfHeaderError::InvalidClass)?;
+            e_phoff = fmt.unpack_u64(&data[EI_NIDENT + 16..EI_NIDENT + 24]).map_err(|_| ElfHeaderError::InvalidClass)?;
+            e_shoff = fmt.unpack_u64(&data[EI_NIDENT + 24..EI_NIDENT + 32]).map_err(|_| ElfHeaderError::InvalidClass)?;
+            rest = EI_NIDENT + 32;
+        } else {
+            e_entry = fmt.unpack_u32(&data[EI_NIDENT + 8..EI_NIDENT + 12]).map_err(|_| ElfHeaderError::InvalidClass)? as u64;
+            e_phoff = fmt.unpack_u32(&data[EI_NIDENT + 12..EI_NIDENT + 16]).map_err(|_| ElfHeaderError::InvalidClass)? as u64;
+            e_shoff = fmt.unpack_u32(&data[EI_NIDENT + 16..EI_NIDENT + 20]).map_err(|_| ElfHeaderError::InvalidClass)? as u64;
+            rest = EI_NIDENT + 20;
         }
-        if e_phoff != 0 {
-            return Err(ElfHeaderError::InvalidProgramHeaderOffset);
+
+        let e_flags = fmt.unpack_u32(&data[rest..rest + 4]).map_err(|_| ElfHeaderError::InvalidClass)?;
+        let e_ehsize = fmt.unpack_u16(&data[rest + 4..rest + 6]).map_err(|_| ElfHeaderError::InvalidClass)?;
+        let e_phentsize = fmt.unpack_u16(&data[rest + 6..rest + 8]).map_err(|_| ElfHeaderError::InvalidClass)?;
+        let e_phnum = fmt.unpack_u16(&data[rest + 8..rest + 10]).map_err(|_| ElfHeaderError::InvalidClass)?;
+        let e_shentsize = fmt.unpack_u16(&data[rest + 10..rest + 12]).map_err(|_| ElfHeaderError::InvalidClass)?;
+        let e_shnum = fmt.unpack_u16(&data[rest + 12..rest + 14]).map_err(|_| ElfHeaderError::InvalidClass)?;
+        let e_shstrndx = fmt.unpack_u16(&data[rest + 14..rest + 16]).map_err(|_| ElfHeaderError::InvalidClass)?;
+
+        // Validate fields. e_type/e_machine/e_phoff assert the MIPS
+        // relocatable-object shape the processing pipeline requires, so
+        // lenient mode (reading arbitrary ELF objects) skips them; the
+        // remaining fields are structural and always checked.
+        if strict {
+            if e_type != 1 {
+                return Err(ElfHeaderError::InvalidType);
+            }
+            if e_machine != 8 {
+                return Err(ElfHeaderError::InvalidMachine);
+            }
+            if e_phoff != 0 {
+                return Err(ElfHeaderError::InvalidProgramHeaderOffset);
+            }
         }
         if e_shoff == 0 {
             return Err(ElfHeaderError::InvalidSectionHeaderOffset);
@@ -100,15 +158,51 @@ impl ElfHeader {
         })
     }
 
+    /// Decode the `EF_MIPS_ABI` bits of `e_flags` into the ABI a MIPS
+    /// relocatable object was assembled for (o32/o64/EABI32/EABI64), or
+    /// `None` if the field is unset (older toolchains that predate the ABI
+    /// encoding, which `asm-processor` treats as implicitly o32).
+    pub fn mips_abi(&self) -> Option<u32> {
+        match self.e_flags & EF_MIPS_ABI {
+            0 => None,
+            abi => Some(abi),
+        }
+    }
+
+    /// Decode the `EF_MIPS_ARCH` high bits of `e_flags` into the ISA level
+    /// constant (`EF_MIPS_ARCH_1`, ..., `EF_MIPS_ARCH_64R2`) the object was
+    /// assembled against.
+    pub fn isa_level(&self) -> u32 {
+        self.e_flags & EF_MIPS_ARCH
+    }
+
+    /// Whether `e_flags` has `EF_MIPS_PIC` set (position-independent code).
+    pub fn is_pic(&self) -> bool {
+        self.e_flags & EF_MIPS_PIC != 0
+    }
+
+    /// Whether `e_flags` has `EF_MIPS_CPIC` set (call position-independent
+    /// code conventions observed, even if this object itself isn't PIC).
+    pub fn is_cpic(&self) -> bool {
+        self.e_flags & EF_MIPS_CPIC != 0
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
-        let mut result = Vec::with_capacity(EI_NIDENT + 36);
+        let is_64 = self.fmt.is_64();
+        let mut result = Vec::with_capacity(EI_NIDENT + Self::rest_size(is_64));
         result.extend_from_slice(&self.e_ident);
         result.extend_from_slice(&self.fmt.pack_u16(self.e_type));
         result.extend_from_slice(&self.fmt.pack_u16(self.e_machine));
         result.extend_from_slice(&self.fmt.pack_u32(self.e_version));
-        result.extend_from_slice(&self.fmt.pack_u32(self.e_entry));
-        result.extend_from_slice(&self.fmt.pack_u32(self.e_phoff));
-        result.extend_from_slice(&self.fmt.pack_u32(self.e_shoff));
+        if is_64 {
+            result.extend_from_slice(&self.fmt.pack_u64(self.e_entry));
+            result.extend_from_slice(&self.fmt.pack_u64(self.e_phoff));
+            result.extend_from_slice(&self.fmt.pack_u64(self.e_shoff));
+        } else {
+            result.extend_from_slice(&self.fmt.pack_u32(self.e_entry as u32));
+            result.extend_from_slice(&self.fmt.pack_u32(self.e_phoff as u32));
+            result.extend_from_slice(&self.fmt.pack_u32(self.e_shoff as u32));
+        }
         result.extend_from_slice(&self.fmt.pack_u32(self.e_flags));
         result.extend_from_slice(&self.fmt.pack_u16(self.e_ehsize));
         result.extend_from_slice(&self.fmt.pack_u16(self.e_phentsize));
@@ -129,9 +223,11 @@ mod tests {
         // Set e_ident
         data[EI_CLASS] = 1; // 32-bit
         data[EI_DATA] = 2;  // big-endian
-        
+        data[EI_VERSION] = 1; // EV_CURRENT
+        data[0..4].copy_from_slice(&[0x7f, b'E', b'L', b'F']);
+
         let fmt = ElfFormat::new(true);
-        
+
         // Write header fields
         let offset = EI_NIDENT;
         data[offset..offset + 2].copy_from_slice(&fmt.pack_u16(1)); // e_type (relocatable)
@@ -147,7 +243,34 @@ mod tests {
         data[offset + 30..offset + 32].copy_from_slice(&fmt.pack_u16(40)); // e_shentsize
         data[offset + 32..offset + 34].copy_from_slice(&fmt.pack_u16(3)); // e_shnum
         data[offset + 34..offset + 36].copy_from_slice(&fmt.pack_u16(2)); // e_shstrndx
-        
+
+        data
+    }
+
+    fn create_test_data_64() -> Vec<u8> {
+        let mut data = vec![0; EI_NIDENT + 48];
+        data[EI_CLASS] = 2; // 64-bit
+        data[EI_DATA] = 2;  // big-endian
+        data[EI_VERSION] = 1; // EV_CURRENT
+        data[0..4].copy_from_slice(&[0x7f, b'E', b'L', b'F']);
+
+        let fmt = ElfFormat::new_64(true);
+
+        let offset = EI_NIDENT;
+        data[offset..offset + 2].copy_from_slice(&fmt.pack_u16(1)); // e_type
+        data[offset + 2..offset + 4].copy_from_slice(&fmt.pack_u16(8)); // e_machine
+        data[offset + 4..offset + 8].copy_from_slice(&fmt.pack_u32(1)); // e_version
+        data[offset + 8..offset + 16].copy_from_slice(&fmt.pack_u64(0x1_0000_0000)); // e_entry
+        data[offset + 16..offset + 24].copy_from_slice(&fmt.pack_u64(0)); // e_phoff
+        data[offset + 24..offset + 32].copy_from_slice(&fmt.pack_u64(64)); // e_shoff
+        data[offset + 32..offset + 36].copy_from_slice(&fmt.pack_u32(0)); // e_flags
+        data[offset + 36..offset + 38].copy_from_slice(&fmt.pack_u16(64)); // e_ehsize
+        data[offset + 38..offset + 40].copy_from_slice(&fmt.pack_u16(0)); // e_phentsize
+        data[offset + 40..offset + 42].copy_from_slice(&fmt.pack_u16(0)); // e_phnum
+        data[offset + 42..offset + 44].copy_from_slice(&fmt.pack_u16(64)); // e_shentsize
+        data[offset + 44..offset + 46].copy_from_slice(&fmt.pack_u16(3)); // e_shnum
+        data[offset + 46..offset + 48].copy_from_slice(&fmt.pack_u16(2)); // e_shstrndx
+
         data
     }
 
@@ -155,7 +278,7 @@ mod tests {
     fn test_elf_header_parse() {
         let data = create_test_data();
         let header = ElfHeader::new(&data).unwrap();
-        
+
         assert_eq!(header.e_type, 1);
         assert_eq!(header.e_machine, 8);
         assert_eq!(header.e_shoff, 52);
@@ -167,15 +290,42 @@ mod tests {
         let data = create_test_data();
         let header = ElfHeader::new(&data).unwrap();
         let bytes = header.to_bytes();
-        
+
         assert_eq!(data, bytes);
     }
 
     #[test]
     fn test_invalid_class() {
         let mut data = create_test_data();
-        data[EI_CLASS] = 2; // Set to 64-bit
-        assert!(matches!(ElfHeader::new(&data), Err(ElfHeaderError::InvalidClass)));
+        data[EI_CLASS] = 3; // Neither 32- nor 64-bit
+        assert!(matches!(ElfHeader::new(&data), Err(ElfHeaderError::InvalidIdent(_))));
+    }
+
+    #[test]
+    fn test_invalid_data_encoding() {
+        let mut data = create_test_data();
+        data[EI_DATA] = 0; // ELFDATANONE
+        assert!(matches!(ElfHeader::new(&data), Err(ElfHeaderError::InvalidIdent(_))));
+    }
+
+    #[test]
+    fn test_invalid_magic() {
+        let mut data = create_test_data();
+        data[0] = 0x00; // not 0x7f
+        assert!(matches!(ElfHeader::new(&data), Err(ElfHeaderError::InvalidMagic)));
+    }
+
+    #[test]
+    fn test_invalid_ident_version() {
+        let mut data = create_test_data();
+        data[EI_VERSION] = 0;
+        assert!(matches!(ElfHeader::new(&data), Err(ElfHeaderError::InvalidIdentVersion)));
+    }
+
+    #[test]
+    fn test_truncated_ident() {
+        let data = vec![0x7f, b'E', b'L', b'F'];
+        assert!(matches!(ElfHeader::new(&data), Err(ElfHeaderError::InvalidIdent(_))));
     }
 
     #[test]
@@ -185,4 +335,66 @@ mod tests {
         data[EI_NIDENT..EI_NIDENT + 2].copy_from_slice(&fmt.pack_u16(2)); // Not relocatable
         assert!(matches!(ElfHeader::new(&data), Err(ElfHeaderError::InvalidType)));
     }
+
+    #[test]
+    fn test_parse_lenient_accepts_non_mips_executable() {
+        let mut data = create_test_data();
+        let fmt = ElfFormat::new(true);
+        data[EI_NIDENT..EI_NIDENT + 2].copy_from_slice(&fmt.pack_u16(2)); // ET_EXEC
+        data[EI_NIDENT + 2..EI_NIDENT + 4].copy_from_slice(&fmt.pack_u16(3)); // EM_386
+        data[EI_NIDENT + 12..EI_NIDENT + 16].copy_from_slice(&fmt.pack_u32(52)); // e_phoff
+
+        let header = ElfHeader::parse_lenient(&data).unwrap();
+        assert_eq!(header.e_type, 2);
+        assert_eq!(header.e_machine, 3);
+    }
+
+    #[test]
+    fn test_parse_lenient_still_checks_structural_fields() {
+        let mut data = create_test_data();
+        data[0] = 0x00; // not 0x7f
+        assert!(matches!(ElfHeader::parse_lenient(&data), Err(ElfHeaderError::InvalidMagic)));
+
+        let mut data = create_test_data();
+        let fmt = ElfFormat::new(true);
+        data[EI_NIDENT + 16..EI_NIDENT + 20].copy_from_slice(&fmt.pack_u32(0)); // e_shoff
+        assert!(matches!(
+            ElfHeader::parse_lenient(&data),
+            Err(ElfHeaderError::InvalidSectionHeaderOffset)
+        ));
+    }
+
+    #[test]
+    fn test_elf64_header_parse_and_roundtrip() {
+        let data = create_test_data_64();
+        let header = ElfHeader::new(&data).unwrap();
+
+        assert!(header.fmt.is_64());
+        assert_eq!(header.e_entry, 0x1_0000_0000);
+        assert_eq!(header.e_shoff, 64);
+        assert_eq!(header.e_shentsize, 64);
+        assert_eq!(header.to_bytes(), data);
+    }
+
+    #[test]
+    fn test_mips_e_flags_decoding() {
+        let mut data = create_test_data();
+        let fmt = ElfFormat::new(true);
+        let offset = EI_NIDENT + 20;
+        data[offset..offset + 4].copy_from_slice(&fmt.pack_u32(EF_MIPS_ABI_O32 | EF_MIPS_ARCH_1 | EF_MIPS_PIC | EF_MIPS_CPIC));
+
+        let header = ElfHeader::new(&data).unwrap();
+        assert_eq!(header.mips_abi(), Some(EF_MIPS_ABI_O32));
+        assert_eq!(header.isa_level(), EF_MIPS_ARCH_1);
+        assert!(header.is_pic());
+        assert!(header.is_cpic());
+    }
+
+    #[test]
+    fn test_mips_e_flags_no_abi() {
+        let data = create_test_data();
+        let header = ElfHeader::new(&data).unwrap();
+        assert_eq!(header.mips_abi(), None);
+        assert!(!header.is_pic());
+    }
 }
@@ -0,0 +1,92 @@
+use crate::elf::format::ElfFormat;
+use crate::utils::Error;
+
+/// The `.MIPS.abiflags` section (`Elf_MIPS_ABIFlags_v0`): a fixed 24-byte
+/// record describing the ABI and ISA extensions an o32/n32/n64 MIPS object
+/// was built against, in more detail than `e_flags` alone can carry (FP
+/// calling convention, ASEs in use, GPR/FPR widths). Newer MIPS toolchains
+/// emit one per object. This type only parses and packs the record; the
+/// post-processing pipeline does not read it or compare the two objects' ABIs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MipsAbiFlags {
+    pub version: u16,
+    pub isa_level: u8,
+    pub isa_rev: u8,
+    pub gpr_size: u8,
+    pub cpr1_size: u8,
+    pub cpr2_size: u8,
+    pub fp_abi: u8,
+    pub isa_ext: u32,
+    pub ases: u32,
+    pub flags1: u32,
+    pub flags2: u32,
+}
+
+impl MipsAbiFlags {
+    /// Size in bytes of the on-disk record.
+    pub const SIZE: usize = 24;
+
+    /// Parse a `.MIPS.abiflags` section's contents.
+    pub fn new(fmt: ElfFormat, data: &[u8]) -> Result<Self, Error> {
+        let mut r = fmt.reader(data);
+        Ok(Self {
+            version: r.u16()?,
+            isa_level: r.u8()?,
+            isa_rev: r.u8()?,
+            gpr_size: r.u8()?,
+            cpr1_size: r.u8()?,
+            cpr2_size: r.u8()?,
+            fp_abi: r.u8()?,
+            isa_ext: r.u32()?,
+            ases: r.u32()?,
+            flags1: r.u32()?,
+            flags2: r.u32()?,
+        })
+    }
+
+    /// Pack back into the 24-byte on-disk record.
+    pub fn to_bytes(&self, fmt: ElfFormat) -> Vec<u8> {
+        let mut w = fmt.writer();
+        w.push_u16(self.version);
+        w.push_u8(self.isa_level);
+        w.push_u8(self.isa_rev);
+        w.push_u8(self.gpr_size);
+        w.push_u8(self.cpr1_size);
+        w.push_u8(self.cpr2_size);
+        w.push_u8(self.fp_abi);
+        w.push_u32(self.isa_ext);
+        w.push_u32(self.ases);
+        w.push_u32(self.flags1);
+        w.push_u32(self.flags2);
+        w.into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mips_abiflags_roundtrip() {
+        let fmt = ElfFormat::new(true);
+        let flags = MipsAbiFlags {
+            version: 0,
+            isa_level: 1,
+            isa_rev: 0,
+            gpr_size: 1, // AFL_REG_32
+            cpr1_size: 1,
+            cpr2_size: 0,
+            fp_abi: 0, // Val_GNU_MIPS_ABI_FP_ANY
+            isa_ext: 0,
+            ases: 0,
+            flags1: 0,
+            flags2: 0,
+        };
+
+        let data = flags.to_bytes(fmt);
+        assert_eq!(data.len(), MipsAbiFlags::SIZE);
+
+        let parsed = MipsAbiFlags::new(fmt, &data).unwrap();
+        assert_eq!(parsed, flags);
+    }
+}
@@ -1,15 +1,27 @@
+pub mod builder;
 pub mod constants;
+pub mod endian;
 pub mod file;
 pub mod format;
 pub mod header;
+pub mod mips_abiflags;
+pub mod note;
+#[cfg(feature = "backend-object")]
+pub mod object_backend;
 pub mod relocation;
 pub mod section;
 pub mod symbol;
 
 // Re-export commonly used types
+pub use builder::{ElfBuilder, SectionHandle};
+pub use endian::{BigEndian, Endian, Endianness, LittleEndian};
 pub use file::ElfFile;
 pub use format::ElfFormat;
 pub use header::ElfHeader;
+pub use mips_abiflags::MipsAbiFlags;
+pub use note::{Note, NoteIterator};
+#[cfg(feature = "backend-object")]
+pub use object_backend::read_elf_file;
 pub use relocation::Relocation;
 pub use section::Section;
 pub use symbol::Symbol;
@@ -0,0 +1,112 @@
+use crate::elf::format::ElfFormat;
+use crate::utils::Error;
+
+/// A single `Elf_Nhdr` record from an `SHT_NOTE` section
+/// (`.note.gnu.build-id`, `.note.ABI-tag`, and similar): a name (e.g.
+/// `"GNU"`), a vendor-defined `n_type`, and an opaque descriptor blob.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Note {
+    /// The note's name, without its trailing NUL (which [`Note::to_bytes`]
+    /// adds back before padding).
+    pub name: String,
+    pub n_type: u32,
+    pub desc: Vec<u8>,
+}
+
+/// Round a size up to the next multiple of 4, the alignment every field in
+/// the note layout (name and descriptor alike) is padded to.
+fn pad4(size: usize) -> usize {
+    (size + 3) & !3
+}
+
+impl Note {
+    /// Serialize back to the on-disk `Elf_Nhdr` layout: `namesz`/`descsz`/
+    /// `n_type` as three words in `fmt`'s endianness, then the NUL-terminated
+    /// name and the descriptor, each padded with zero bytes to a 4-byte
+    /// boundary.
+    pub fn to_bytes(&self, fmt: ElfFormat) -> Vec<u8> {
+        let namesz = self.name.len() + 1;
+        let mut w = fmt.writer();
+        w.push_u32(namesz as u32);
+        w.push_u32(self.desc.len() as u32);
+        w.push_u32(self.n_type);
+        w.push_bytes(self.name.as_bytes());
+        w.push_bytes(&[0]);
+        w.push_bytes(&vec![0u8; pad4(namesz) - namesz]);
+        w.push_bytes(&self.desc);
+        w.push_bytes(&vec![0u8; pad4(self.desc.len()) - self.desc.len()]);
+        w.into_bytes()
+    }
+}
+
+/// Walks the zero or more `Elf_Nhdr` records packed back-to-back in an
+/// `SHT_NOTE` section's raw data, the same layout the `object` crate exposes
+/// via its own note reader.
+pub struct NoteIterator<'a> {
+    fmt: ElfFormat,
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> NoteIterator<'a> {
+    pub fn new(fmt: ElfFormat, data: &'a [u8]) -> Self {
+        Self { fmt, data, offset: 0 }
+    }
+
+    fn next_note(&mut self) -> Result<Note, Error> {
+        let mut r = self.fmt.reader(&self.data[self.offset..]);
+        let namesz = r.u32()? as usize;
+        let descsz = r.u32()? as usize;
+        let n_type = r.u32()?;
+
+        let name_bytes = r.bytes(namesz)?;
+        let name = String::from_utf8_lossy(name_bytes.strip_suffix(&[0]).unwrap_or(name_bytes)).into_owned();
+        r.bytes(pad4(namesz) - namesz)?;
+
+        let desc = r.bytes(descsz)?.to_vec();
+        r.bytes(pad4(descsz) - descsz)?;
+
+        self.offset += r.offset();
+        Ok(Note { name, n_type, desc })
+    }
+}
+
+impl<'a> Iterator for NoteIterator<'a> {
+    type Item = Result<Note, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.data.len() {
+            return None;
+        }
+        Some(self.next_note())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_note_roundtrip() {
+        let fmt = ElfFormat::new(true);
+        let note = Note { name: "GNU".to_string(), n_type: 3, desc: vec![0xde, 0xad, 0xbe, 0xef, 0x01] };
+
+        let data = note.to_bytes(fmt);
+        let parsed: Vec<Note> = NoteIterator::new(fmt, &data).collect::<Result<_, _>>().unwrap();
+
+        assert_eq!(parsed, vec![note]);
+    }
+
+    #[test]
+    fn test_note_iterator_multiple_notes() {
+        let fmt = ElfFormat::new(true);
+        let a = Note { name: "GNU".to_string(), n_type: 3, desc: vec![1, 2, 3] };
+        let b = Note { name: "GNU".to_string(), n_type: 1, desc: vec![4, 5, 6, 7] };
+
+        let mut data = a.to_bytes(fmt);
+        data.extend(b.to_bytes(fmt));
+
+        let parsed: Vec<Note> = NoteIterator::new(fmt, &data).collect::<Result<_, _>>().unwrap();
+        assert_eq!(parsed, vec![a, b]);
+    }
+}
@@ -1,29 +1,61 @@
 use crate::elf::format::ElfFormat;
 use crate::elf::constants::SHT_REL;
 
+/// Represents an ELF relocation entry (`Elf32_Rel`/`Elf32_Rela` or their
+/// 64-bit counterparts).
+///
+/// The `r_info` split differs between the two: `Elf32_Rel` packs
+/// `sym_index = r_info >> 8`, `rel_type = r_info & 0xff`, while
+/// `Elf64_Rel` packs `sym_index = r_info >> 32`, `rel_type = r_info &
+/// 0xffff_ffff`. `r_addend` also widens from `i32` to `i64` and is only
+/// present for the `Rela` variant (`sh_type != SHT_REL`).
 #[derive(Debug, Clone)]
 pub struct Relocation {
-    pub r_offset: u32,
-    pub r_info: u32,
-    pub r_addend: Option<u32>,
+    pub r_offset: u64,
+    pub r_info: u64,
+    pub r_addend: Option<i64>,
     pub sym_index: u32,
-    pub rel_type: u8,
+    pub rel_type: u32,
     fmt: ElfFormat,
     sh_type: u32,
 }
 
 impl Relocation {
+    /// Size in bytes of a relocation entry for this format/`sh_type`: 8/16
+    /// for `Elf32_Rel`/`Elf64_Rel`, 12/24 for `Elf32_Rela`/`Elf64_Rela`.
+    pub fn entry_size(fmt: ElfFormat, sh_type: u32) -> usize {
+        match (fmt.is_64(), sh_type == SHT_REL) {
+            (false, true) => 8,
+            (false, false) => 12,
+            (true, true) => 16,
+            (true, false) => 24,
+        }
+    }
+
     pub fn new(fmt: ElfFormat, data: &[u8], sh_type: u32) -> Self {
-        let (r_offset, r_info, r_addend) = if sh_type == SHT_REL {
-            let (offset, info) = fmt.unpack_tuple_u32(data);
-            (offset, info, None)
+        let (r_offset, r_info, r_addend) = if fmt.is_64() {
+            if sh_type == SHT_REL {
+                let (offset, info) = Self::unpack_tuple_u64(fmt, data);
+                (offset, info, None)
+            } else {
+                let offset = fmt.unpack_u64(&data[0..8]).unwrap_or(0);
+                let info = fmt.unpack_u64(&data[8..16]).unwrap_or(0);
+                let addend = fmt.unpack_u64(&data[16..24]).unwrap_or(0) as i64;
+                (offset, info, Some(addend))
+            }
+        } else if sh_type == SHT_REL {
+            let (offset, info) = fmt.unpack_tuple_u32(data).unwrap_or((0, 0));
+            (offset as u64, info as u64, None)
         } else {
-            let (offset, info, addend) = fmt.unpack_tuple_u32_3(data);
-            (offset, info, Some(addend))
+            let (offset, info, addend) = fmt.unpack_tuple_u32_3(data).unwrap_or((0, 0, 0));
+            (offset as u64, info as u64, Some(addend as i32 as i64))
         };
 
-        let sym_index = r_info >> 8;
-        let rel_type = (r_info & 0xff) as u8;
+        let (sym_index, rel_type) = if fmt.is_64() {
+            ((r_info >> 32) as u32, (r_info & 0xffff_ffff) as u32)
+        } else {
+            ((r_info >> 8) as u32, (r_info & 0xff) as u32)
+        };
 
         Self {
             r_offset,
@@ -36,19 +68,43 @@ impl Relocation {
         }
     }
 
+    fn unpack_tuple_u64(fmt: ElfFormat, data: &[u8]) -> (u64, u64) {
+        (
+            fmt.unpack_u64(&data[0..8]).unwrap_or(0),
+            fmt.unpack_u64(&data[8..16]).unwrap_or(0),
+        )
+    }
+
     pub fn to_bytes(&mut self) -> Vec<u8> {
-        self.r_info = (self.sym_index << 8) | (self.rel_type as u32);
-        
-        if self.sh_type == SHT_REL {
+        self.r_info = if self.fmt.is_64() {
+            ((self.sym_index as u64) << 32) | (self.rel_type as u64)
+        } else {
+            ((self.sym_index as u64) << 8) | (self.rel_type as u64 & 0xff)
+        };
+
+        if self.fmt.is_64() {
+            if self.sh_type == SHT_REL {
+                let mut result = Vec::with_capacity(16);
+                result.extend_from_slice(&self.fmt.pack_u64(self.r_offset));
+                result.extend_from_slice(&self.fmt.pack_u64(self.r_info));
+                result
+            } else {
+                let mut result = Vec::with_capacity(24);
+                result.extend_from_slice(&self.fmt.pack_u64(self.r_offset));
+                result.extend_from_slice(&self.fmt.pack_u64(self.r_info));
+                result.extend_from_slice(&self.fmt.pack_u64(self.r_addend.unwrap_or(0) as u64));
+                result
+            }
+        } else if self.sh_type == SHT_REL {
             let mut result = Vec::with_capacity(8);
-            result.extend_from_slice(&self.fmt.pack_u32(self.r_offset));
-            result.extend_from_slice(&self.fmt.pack_u32(self.r_info));
+            result.extend_from_slice(&self.fmt.pack_u32(self.r_offset as u32));
+            result.extend_from_slice(&self.fmt.pack_u32(self.r_info as u32));
             result
         } else {
             let mut result = Vec::with_capacity(12);
-            result.extend_from_slice(&self.fmt.pack_u32(self.r_offset));
-            result.extend_from_slice(&self.fmt.pack_u32(self.r_info));
-            result.extend_from_slice(&self.fmt.pack_u32(self.r_addend.unwrap_or(0)));
+            result.extend_from_slice(&self.fmt.pack_u32(self.r_offset as u32));
+            result.extend_from_slice(&self.fmt.pack_u32(self.r_info as u32));
+            result.extend_from_slice(&self.fmt.pack_u32(self.r_addend.unwrap_or(0) as u32));
             result
         }
     }
@@ -111,4 +167,37 @@ mod tests {
         let bytes = rel.to_bytes();
         assert_eq!(data, bytes);
     }
+
+    #[test]
+    fn test_rel64_roundtrip() {
+        let fmt = ElfFormat::new_64(true);
+        let mut data = Vec::new();
+        data.extend_from_slice(&fmt.pack_u64(0x1000));
+        data.extend_from_slice(&fmt.pack_u64((2u64 << 32) | 5)); // sym_index = 2, type = 5
+
+        let mut rel = Relocation::new(fmt, &data, SHT_REL);
+        assert_eq!(rel.sym_index, 2);
+        assert_eq!(rel.rel_type, 5);
+        assert!(rel.r_addend.is_none());
+
+        let bytes = rel.to_bytes();
+        assert_eq!(data, bytes);
+    }
+
+    #[test]
+    fn test_rela64_roundtrip() {
+        let fmt = ElfFormat::new_64(true);
+        let mut data = Vec::new();
+        data.extend_from_slice(&fmt.pack_u64(0x2000));
+        data.extend_from_slice(&fmt.pack_u64((4u64 << 32) | 8)); // sym_index = 4, type = 8
+        data.extend_from_slice(&fmt.pack_u64((-0x10i64) as u64)); // negative addend
+
+        let mut rel = Relocation::new(fmt, &data, SHT_REL + 1);
+        assert_eq!(rel.sym_index, 4);
+        assert_eq!(rel.rel_type, 8);
+        assert_eq!(rel.r_addend, Some(-0x10));
+
+        let bytes = rel.to_bytes();
+        assert_eq!(data, bytes);
+    }
 }
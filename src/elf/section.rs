@@ -7,23 +7,71 @@ use crate::elf::constants::*;
 const SHF_LINK_ORDER: u32 = 0x80;
 
 pub trait Section {
-    fn lookup_str(&self, index: usize) -> Result<String, Error>;
+    /// Look up the NUL-terminated string starting at `offset` in this
+    /// string table section. Panics (via slice indexing) on a malformed
+    /// table, matching the Python tool's behavior of trusting its own
+    /// output.
+    fn lookup_str(&self, offset: u32) -> String;
     fn add_str(&mut self, s: &str) -> Result<u32, Error>;
 }
 
+/// Shifts file-relative offsets embedded in a debug section's own payload
+/// by however far the section itself moved, letting [`ElfSection::relocate_mdebug`]
+/// (MIPS `.mdebug`) and [`ElfSection::relocate_debug_line`] (DWARF
+/// `.debug_line`) both be invoked uniformly from the writer when a
+/// section's `sh_offset` changes.
+pub trait DebugRelocator {
+    fn relocate_debug(&mut self, original_offset: u64) -> Result<(), Error>;
+}
+
+impl DebugRelocator for ElfSection {
+    fn relocate_debug(&mut self, original_offset: u64) -> Result<(), Error> {
+        if self.sh_type == SHT_MIPS_DEBUG {
+            self.relocate_mdebug(original_offset)
+        } else if self.name == ".debug_line" {
+            self.relocate_debug_line(original_offset)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Decode a ULEB128 value from the start of `data`, returning `(value,
+/// byte_length)`, or `None` if `data` ends before a terminating byte (one
+/// with the high bit clear) is found.
+fn read_uleb128(data: &[u8]) -> Option<(u64, usize)> {
+    let mut result: u64 = 0;
+    let mut shift = 0;
+    for (i, &byte) in data.iter().enumerate() {
+        result |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Some((result, i + 1));
+        }
+        shift += 7;
+    }
+    None
+}
+
+/// An ELF section header (`Elf32_Shdr`/`Elf64_Shdr`) plus its data and,
+/// once [`ElfSection::late_init`] has run, its parsed symbols/relocations.
+///
+/// `sh_flags`/`sh_addr`/`sh_offset`/`sh_size`/`sh_addralign`/`sh_entsize`
+/// are stored widened to `u64` so the same struct covers both the 32-bit
+/// (40-byte) and 64-bit (64-byte) layouts; [`ElfSection::fmt`]'s
+/// [`ElfFormat::is_64`] records which one this section was parsed from.
 #[derive(Debug, Clone)]
 pub struct ElfSection {
     pub fmt: ElfFormat,
     pub sh_name: u32,
     pub sh_type: u32,
-    pub sh_flags: u32,
-    pub sh_addr: u32,
-    pub sh_offset: u32,
-    pub sh_size: u32,
+    pub sh_flags: u64,
+    pub sh_addr: u64,
+    pub sh_offset: u64,
+    pub sh_size: u64,
     pub sh_link: u32,
     pub sh_info: u32,
-    pub sh_addralign: u32,
-    pub sh_entsize: u32,
+    pub sh_addralign: u64,
+    pub sh_entsize: u64,
     pub data: Vec<u8>,
     pub symbols: Vec<Symbol>,
     pub relocations: Vec<Relocation>,
@@ -33,21 +81,41 @@ pub struct ElfSection {
 }
 
 impl ElfSection {
+    /// Size in bytes of a section header for `fmt` (40 for `Elf32_Shdr`,
+    /// 64 for `Elf64_Shdr`).
+    pub fn header_size(fmt: ElfFormat) -> usize {
+        if fmt.is_64() { 64 } else { 40 }
+    }
+
     pub fn new(fmt: ElfFormat, header: &[u8]) -> Result<Self, Error> {
-        if header.len() < 40 {
+        let header_size = Self::header_size(fmt);
+        if header.len() < header_size {
             return Err(Error::InvalidFormat("Section header too short".into()));
         }
 
         let sh_name = fmt.unpack_u32(&header[0..4])?;
         let sh_type = fmt.unpack_u32(&header[4..8])?;
-        let sh_flags = fmt.unpack_u32(&header[8..12])?;
-        let sh_addr = fmt.unpack_u32(&header[12..16])?;
-        let sh_offset = fmt.unpack_u32(&header[16..20])?;
-        let sh_size = fmt.unpack_u32(&header[20..24])?;
-        let sh_link = fmt.unpack_u32(&header[24..28])?;
-        let sh_info = fmt.unpack_u32(&header[28..32])?;
-        let sh_addralign = fmt.unpack_u32(&header[32..36])?;
-        let sh_entsize = fmt.unpack_u32(&header[36..40])?;
+
+        let (sh_flags, sh_addr, sh_offset, sh_size, sh_link, sh_info, sh_addralign, sh_entsize);
+        if fmt.is_64() {
+            sh_flags = fmt.unpack_u64(&header[8..16])?;
+            sh_addr = fmt.unpack_u64(&header[16..24])?;
+            sh_offset = fmt.unpack_u64(&header[24..32])?;
+            sh_size = fmt.unpack_u64(&header[32..40])?;
+            sh_link = fmt.unpack_u32(&header[40..44])?;
+            sh_info = fmt.unpack_u32(&header[44..48])?;
+            sh_addralign = fmt.unpack_u64(&header[48..56])?;
+            sh_entsize = fmt.unpack_u64(&header[56..64])?;
+        } else {
+            sh_flags = fmt.unpack_u32(&header[8..12])? as u64;
+            sh_addr = fmt.unpack_u32(&header[12..16])? as u64;
+            sh_offset = fmt.unpack_u32(&header[16..20])? as u64;
+            sh_size = fmt.unpack_u32(&header[20..24])? as u64;
+            sh_link = fmt.unpack_u32(&header[24..28])?;
+            sh_info = fmt.unpack_u32(&header[28..32])?;
+            sh_addralign = fmt.unpack_u32(&header[32..36])? as u64;
+            sh_entsize = fmt.unpack_u32(&header[36..40])? as u64;
+        }
 
         Ok(Self {
             fmt,
@@ -70,8 +138,8 @@ impl ElfSection {
         })
     }
 
-    pub fn from_parts(fmt: ElfFormat, sh_name: u32, sh_type: u32, sh_flags: u32, sh_link: u32, 
-                     sh_info: u32, sh_addralign: u32, sh_entsize: u32, data: Vec<u8>, index: usize) -> Self {
+    pub fn from_parts(fmt: ElfFormat, sh_name: u32, sh_type: u32, sh_flags: u64, sh_link: u32,
+                     sh_info: u32, sh_addralign: u64, sh_entsize: u64, data: Vec<u8>, index: usize) -> Self {
         Self {
             fmt,
             sh_name,
@@ -79,7 +147,7 @@ impl ElfSection {
             sh_flags,
             sh_addr: 0,
             sh_offset: 0,
-            sh_size: data.len() as u32,
+            sh_size: data.len() as u64,
             sh_link,
             sh_info,
             sh_addralign,
@@ -98,74 +166,49 @@ impl ElfSection {
     }
 
     pub fn to_bytes(&self) -> Vec<u8> {
-        let mut data = vec![0; 40];
-        let fmt = ElfFormat::new(true);
-        
         // Update sh_size to match data length if needed
         let size = if self.sh_type != SHT_NOBITS && !self.data.is_empty() {
-            self.data.len() as u32
+            self.data.len() as u64
         } else {
             self.sh_size
         };
-        
-        let mut tmp = [0; 4];
-        
-        // sh_name
-        fmt.pack_u32(&mut tmp, self.sh_name).unwrap();
-        data[0..4].copy_from_slice(&tmp);
-        
-        // sh_type
-        fmt.pack_u32(&mut tmp, self.sh_type).unwrap();
-        data[4..8].copy_from_slice(&tmp);
-        
-        // sh_flags
-        fmt.pack_u32(&mut tmp, self.sh_flags).unwrap();
-        data[8..12].copy_from_slice(&tmp);
-        
-        // sh_addr
-        fmt.pack_u32(&mut tmp, self.sh_addr).unwrap();
-        data[12..16].copy_from_slice(&tmp);
-        
-        // sh_offset
-        fmt.pack_u32(&mut tmp, self.sh_offset).unwrap();
-        data[16..20].copy_from_slice(&tmp);
-        
-        // sh_size
-        fmt.pack_u32(&mut tmp, size).unwrap();
-        data[20..24].copy_from_slice(&tmp);
-        
-        // sh_link
-        fmt.pack_u32(&mut tmp, self.sh_link).unwrap();
-        data[24..28].copy_from_slice(&tmp);
-        
-        // sh_info
-        fmt.pack_u32(&mut tmp, self.sh_info).unwrap();
-        data[28..32].copy_from_slice(&tmp);
-        
-        // sh_addralign
-        fmt.pack_u32(&mut tmp, self.sh_addralign).unwrap();
-        data[32..36].copy_from_slice(&tmp);
-        
-        // sh_entsize
-        fmt.pack_u32(&mut tmp, self.sh_entsize).unwrap();
-        data[36..40].copy_from_slice(&tmp);
-        
+
+        let mut data = Vec::with_capacity(Self::header_size(self.fmt));
+        data.extend_from_slice(&self.fmt.pack_u32(self.sh_name));
+        data.extend_from_slice(&self.fmt.pack_u32(self.sh_type));
+        if self.fmt.is_64() {
+            data.extend_from_slice(&self.fmt.pack_u64(self.sh_flags));
+            data.extend_from_slice(&self.fmt.pack_u64(self.sh_addr));
+            data.extend_from_slice(&self.fmt.pack_u64(self.sh_offset));
+            data.extend_from_slice(&self.fmt.pack_u64(size));
+            data.extend_from_slice(&self.fmt.pack_u32(self.sh_link));
+            data.extend_from_slice(&self.fmt.pack_u32(self.sh_info));
+            data.extend_from_slice(&self.fmt.pack_u64(self.sh_addralign));
+            data.extend_from_slice(&self.fmt.pack_u64(self.sh_entsize));
+        } else {
+            data.extend_from_slice(&self.fmt.pack_u32(self.sh_flags as u32));
+            data.extend_from_slice(&self.fmt.pack_u32(self.sh_addr as u32));
+            data.extend_from_slice(&self.fmt.pack_u32(self.sh_offset as u32));
+            data.extend_from_slice(&self.fmt.pack_u32(size as u32));
+            data.extend_from_slice(&self.fmt.pack_u32(self.sh_link));
+            data.extend_from_slice(&self.fmt.pack_u32(self.sh_info));
+            data.extend_from_slice(&self.fmt.pack_u32(self.sh_addralign as u32));
+            data.extend_from_slice(&self.fmt.pack_u32(self.sh_entsize as u32));
+        }
         data
     }
 
     pub fn to_test_data(&self) -> Vec<u8> {
-        let mut data = vec![0; 40];
-        let fmt = ElfFormat::new(true);
-        
-        let mut tmp = [0; 4];
-        
-        // Pack test values 1-10 into the buffer
-        for i in 0..10 {
-            fmt.pack_u32(&mut tmp, i as u32 + 1).unwrap();
+        let header_size = Self::header_size(self.fmt);
+        let mut data = vec![0; header_size];
+
+        // Pack test values 1-N into the buffer as consecutive u32s
+        for i in 0..(header_size / 4) {
+            let tmp = self.fmt.pack_u32(i as u32 + 1);
             let start = i * 4;
-            data[start..start+4].copy_from_slice(&tmp);
+            data[start..start + 4].copy_from_slice(&tmp);
         }
-        
+
         data
     }
 
@@ -177,23 +220,23 @@ impl ElfSection {
         // Convert to latin1 bytes like Python
         self.data.extend(string.chars().map(|c| c as u8));
         self.data.push(0);
-        self.sh_size = self.data.len() as u32;  // Update sh_size to match data length
+        self.sh_size = self.data.len() as u64;  // Update sh_size to match data length
         Ok(ret)
     }
 
-    pub fn find_symbol(&self, name: &str, sections: &[ElfSection]) -> Result<Option<(usize, u32)>, Error> {
+    pub fn find_symbol(&self, name: &str, sections: &[ElfSection]) -> Result<Option<(usize, u64)>, Error> {
         if self.sh_type != SHT_SYMTAB {
             return Err(Error::InvalidSection("Not a symbol table section".into()));
         }
         for symbol in &self.symbols {
             if symbol.name == name {
-                return Ok(Some((symbol.st_shndx as usize, symbol.st_value)));
+                return Ok(Some((symbol.shndx32 as usize, symbol.st_value)));
             }
         }
         Ok(None)
     }
 
-    pub fn find_symbol_in_section(&self, name: &str, section: &ElfSection) -> Result<u32, Error> {
+    pub fn find_symbol_in_section(&self, name: &str, section: &ElfSection) -> Result<u64, Error> {
         let pos = self.find_symbol(name, &[section.clone()])?
             .ok_or_else(|| Error::InvalidSection("Symbol not found".into()))?;
         if pos.0 != section.index {
@@ -218,12 +261,21 @@ impl ElfSection {
 
     pub fn late_init(&mut self, sections: &mut [ElfSection]) -> Result<(), Error> {
         if self.sh_type == SHT_SYMTAB {
-            self.init_symbols()?;
+            let xindex = sections
+                .iter()
+                .find(|s| s.sh_type == SHT_SYMTAB_SHNDX && s.sh_link as usize == self.index)
+                .map(|s| {
+                    s.data
+                        .chunks_exact(4)
+                        .map(|chunk| self.fmt.unpack_u32(chunk).unwrap_or(0))
+                        .collect::<Vec<u32>>()
+                });
+            self.init_symbols(xindex.as_deref())?;
         } else if self.is_rel() {
             let mut offset = 0;
-            let entry_size = if self.sh_type == SHT_REL { 8 } else { 12 };
+            let entry_size = Relocation::entry_size(self.fmt, self.sh_type);
             while offset + entry_size <= self.data.len() {
-                let relocation = Relocation::new(&self.fmt, &self.data[offset..offset + entry_size], self.sh_type)?;
+                let relocation = Relocation::new(self.fmt, &self.data[offset..offset + entry_size], self.sh_type);
                 self.relocations.push(relocation);
                 offset += entry_size;
             }
@@ -236,24 +288,27 @@ impl ElfSection {
         Ok(())
     }
 
-    pub fn relocate_mdebug(&mut self, original_offset: u32) -> Result<(), Error> {
+    pub fn relocate_mdebug(&mut self, original_offset: u64) -> Result<(), Error> {
         if self.sh_type != SHT_MIPS_DEBUG {
             return Err(Error::InvalidFormat("Not a MIPS_DEBUG section".to_string()));
         }
 
-        let mut new_data = self.data.clone();
-        let shift_by = self.sh_offset.wrapping_sub(original_offset);
+        let shift_by = (self.sh_offset.wrapping_sub(original_offset)) as u32;
 
         // First unpack the magic and version stamp
-        let magic = self.fmt.unpack_u16(&self.data[0..2])?;
-        let vstamp = self.fmt.unpack_u16(&self.data[2..4])?;
+        let mut r = self.fmt.reader(&self.data);
+        let magic = r.u16()?;
+        let vstamp = r.u16()?;
 
         if magic != 0x7009 {
             return Err(Error::InvalidFormat("Invalid magic value for .mdebug symbolic header".to_string()));
         }
 
-        // Now unpack the remaining values
-        let mut values = self.fmt.unpack_tuple_u32(&self.data[4..0x60], 23)?;
+        // Now unpack the remaining values (23 u32 fields)
+        let mut values = [0u32; 23];
+        for v in values.iter_mut() {
+            *v = r.u32()?;
+        }
 
         // Update offsets if count is non-zero (matching Python implementation)
         if values[0] > 0 { values[2] = values[2].wrapping_add(shift_by); }  // ilineMax -> cbLineOffset
@@ -269,30 +324,202 @@ impl ElfSection {
         if values[21] > 0 { values[22] = values[22].wrapping_add(shift_by); }  // iextMax -> cbExtOffset
 
         // Pack everything back
-        self.fmt.pack_u16(&mut new_data[0..2], magic)?;
-        self.fmt.pack_u16(&mut new_data[2..4], vstamp)?;
-        self.fmt.pack_tuple_u32(&mut new_data[4..0x60], &values)?;
+        let mut w = self.fmt.writer();
+        w.push_u16(magic);
+        w.push_u16(vstamp);
+        for v in values {
+            w.push_u32(v);
+        }
+
+        self.data = w.into_bytes();
+        Ok(())
+    }
+
+    /// Shift `DW_LNE_set_address` operands embedded in a `.debug_line`
+    /// section by `self.sh_offset - original_offset`, so the line number
+    /// program's addresses keep agreeing with the bytes they annotate
+    /// after sections are rearranged.
+    ///
+    /// Walks the line number program headers unit by unit via each one's
+    /// `unit_length` (honoring the 64-bit DWARF escape, `0xffffffff`
+    /// followed by an 8-byte length), then scans each unit's program for
+    /// extended opcodes (a `0x00` byte, a ULEB128 length, then the
+    /// sub-opcode) and patches the operand of `DW_LNE_set_address` (2),
+    /// assuming a 4-byte target address as used by o32 MIPS.
+    pub fn relocate_debug_line(&mut self, original_offset: u64) -> Result<(), Error> {
+        let shift_by = self.sh_offset.wrapping_sub(original_offset) as u32;
+        if shift_by == 0 {
+            return Ok(());
+        }
+
+        let mut pos = 0usize;
+        while pos + 4 <= self.data.len() {
+            let initial_len = self.fmt.unpack_u32(&self.data[pos..pos + 4])?;
+            let (unit_length, length_field_size, is_64bit_dwarf) = if initial_len == 0xffff_ffff {
+                if pos + 12 > self.data.len() {
+                    break;
+                }
+                (self.fmt.unpack_u64(&self.data[pos + 4..pos + 12])?, 12, true)
+            } else {
+                (initial_len as u64, 4, false)
+            };
+            if unit_length == 0 {
+                break;
+            }
+
+            let unit_start = pos + length_field_size;
+            let unit_end = unit_start + unit_length as usize;
+            if unit_end > self.data.len() {
+                break;
+            }
+
+            let mut cursor = unit_start + 2; // skip `version`
+            let header_length = if is_64bit_dwarf {
+                let v = self.fmt.unpack_u64(&self.data[cursor..cursor + 8])?;
+                cursor += 8;
+                v
+            } else {
+                let v = self.fmt.unpack_u32(&self.data[cursor..cursor + 4])?;
+                cursor += 4;
+                v as u64
+            };
+
+            let mut p = cursor + header_length as usize;
+            while p < unit_end {
+                if self.data[p] != 0x00 {
+                    p += 1;
+                    continue;
+                }
+                let Some((len, len_bytes)) = read_uleb128(&self.data[p + 1..unit_end]) else {
+                    break;
+                };
+                if len == 0 {
+                    p += 1;
+                    continue;
+                }
+                let ext_start = p + 1 + len_bytes;
+                let sub_opcode = self.data.get(ext_start).copied().unwrap_or(0);
+                if sub_opcode == 0x02 && len >= 5 {
+                    let addr_off = ext_start + 1;
+                    if addr_off + 4 <= self.data.len() {
+                        let addr = self.fmt.unpack_u32(&self.data[addr_off..addr_off + 4])?;
+                        self.data[addr_off..addr_off + 4].copy_from_slice(&self.fmt.pack_u32(addr.wrapping_add(shift_by)));
+                    }
+                }
+                p += 1 + len_bytes + len as usize;
+            }
+
+            pos = unit_end;
+        }
 
-        self.data = new_data;
         Ok(())
     }
 
     pub fn init_data(&mut self, data: &[u8]) -> Result<(), Error> {
-        self.data = data[self.sh_offset as usize..(self.sh_offset + self.sh_size) as usize].to_vec();
+        let start = self.sh_offset as usize;
+        let end = start + self.sh_size as usize;
+        self.data = data[start..end].to_vec();
         Ok(())
     }
 
-    pub fn init_symbols(&mut self) -> Result<(), Error> {
+    /// Size of the `Elf_Chdr` compression header that precedes a
+    /// `SHF_COMPRESSED` section's data: `Elf32_Chdr` is three 32-bit words
+    /// (`ch_type`, `ch_size`, `ch_addralign`); `Elf64_Chdr` is a 32-bit
+    /// `ch_type` and `ch_reserved`, then 64-bit `ch_size`/`ch_addralign`.
+    fn chdr_size(fmt: ElfFormat) -> usize {
+        if fmt.is_64() { 24 } else { 12 }
+    }
+
+    /// If this section has `SHF_COMPRESSED` set, strip the leading
+    /// `Elf_Chdr` and inflate the rest into `self.data`, restoring
+    /// `sh_addralign` from the header and clearing the flag. A no-op
+    /// otherwise.
+    pub fn decompress(&mut self) -> Result<(), Error> {
+        if self.sh_flags & SHF_COMPRESSED as u64 == 0 {
+            return Ok(());
+        }
+
+        let header_size = Self::chdr_size(self.fmt);
+        let mut r = self.fmt.reader(&self.data);
+        let (ch_type, ch_size, ch_addralign) = if self.fmt.is_64() {
+            let ch_type = r.u32()?;
+            r.u32()?; // ch_reserved
+            (ch_type, r.u64()?, r.u64()?)
+        } else {
+            (r.u32()?, r.u32()? as u64, r.u32()? as u64)
+        };
+
+        if ch_type != ELFCOMPRESS_ZLIB {
+            return Err(Error::InvalidSection(format!("unsupported Elf_Chdr ch_type {}", ch_type)));
+        }
+
+        let mut decoder = flate2::read::ZlibDecoder::new(&self.data[header_size..]);
+        let mut decompressed = Vec::with_capacity(ch_size as usize);
+        std::io::Read::read_to_end(&mut decoder, &mut decompressed)
+            .map_err(|e| Error::InvalidSection(format!("failed to inflate section: {}", e)))?;
+
+        self.data = decompressed;
+        self.sh_flags &= !(SHF_COMPRESSED as u64);
+        self.sh_addralign = ch_addralign;
+        self.sh_size = self.data.len() as u64;
+        Ok(())
+    }
+
+    /// Compress `self.data` in place behind a fresh `Elf_Chdr`, setting
+    /// `SHF_COMPRESSED`. The original `sh_addralign` is preserved in the
+    /// header's `ch_addralign` field; `sh_addralign` itself becomes the
+    /// `Elf_Chdr`'s own alignment requirement.
+    pub fn compress(&mut self, ch_type: u32) -> Result<(), Error> {
+        if ch_type != ELFCOMPRESS_ZLIB {
+            return Err(Error::InvalidSection(format!("unsupported Elf_Chdr ch_type {}", ch_type)));
+        }
+        if self.sh_flags & SHF_COMPRESSED as u64 != 0 {
+            return Err(Error::InvalidSection("section is already compressed".into()));
+        }
+
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        std::io::Write::write_all(&mut encoder, &self.data)
+            .map_err(|e| Error::InvalidSection(format!("failed to deflate section: {}", e)))?;
+        let compressed = encoder.finish()
+            .map_err(|e| Error::InvalidSection(format!("failed to deflate section: {}", e)))?;
+
+        let mut w = self.fmt.writer();
+        w.push_u32(ch_type);
+        if self.fmt.is_64() {
+            w.push_u32(0); // ch_reserved
+            w.push_u64(self.data.len() as u64);
+            w.push_u64(self.sh_addralign);
+        } else {
+            w.push_u32(self.data.len() as u32);
+            w.push_u32(self.sh_addralign as u32);
+        }
+        w.push_bytes(&compressed);
+        self.data = w.into_bytes();
+        self.sh_flags |= SHF_COMPRESSED as u64;
+        self.sh_addralign = if self.fmt.is_64() { 8 } else { 4 };
+        self.sh_size = self.data.len() as u64;
+        Ok(())
+    }
+
+    /// Parse this section's symbols. `xindex`, if present, is the unpacked
+    /// `SHT_SYMTAB_SHNDX` table that complements this symbol table, indexed
+    /// in lockstep with it (used to resolve any `st_shndx == SHN_XINDEX`).
+    pub fn init_symbols(&mut self, xindex: Option<&[u32]>) -> Result<(), Error> {
         if self.sh_type != SHT_SYMTAB {
             return Ok(());
         }
 
+        let entry_size = self.fmt.symbol_entry_size();
         let mut symbols = Vec::new();
         let mut offset = 0;
-        while offset + 16 <= self.data.len() {
-            let symbol = Symbol::new(&self.fmt, &self.data[offset..offset + 16], self)?;
+        let mut i = 0;
+        while offset + entry_size <= self.data.len() {
+            let xshndx = xindex.and_then(|table| table.get(i).copied());
+            let symbol = Symbol::new(self.fmt, &self.data[offset..offset + entry_size], self, None, xshndx)
+                .map_err(|e| Error::InvalidSymbol(e.to_string()))?;
             symbols.push(symbol);
-            offset += 16;
+            offset += entry_size;
+            i += 1;
         }
         self.symbols = symbols;
         Ok(())
@@ -304,15 +531,86 @@ impl ElfSection {
         }
 
         let mut offset = 0;
-        let entry_size = if self.sh_type == SHT_REL { 8 } else { 12 };
+        let entry_size = Relocation::entry_size(self.fmt, self.sh_type);
         while offset + entry_size <= self.data.len() {
-            let relocation = Relocation::new(&self.fmt, &self.data[offset..offset + entry_size], self.sh_type)?;
+            let relocation = Relocation::new(self.fmt, &self.data[offset..offset + entry_size], self.sh_type);
             self.relocations.push(relocation);
             offset += entry_size;
         }
         Ok(())
     }
 
+    /// Resolve every relocation recorded against this section (via
+    /// [`Self::relocated_by`]) and patch `self.data` in place, using
+    /// `symtab` to look up each relocation's referenced symbol and
+    /// `sections` to find that symbol's defining section (for its base
+    /// address). Supports the MIPS o32 types the rest of the crate cares
+    /// about: `R_MIPS_32`, `R_MIPS_26`, and the `R_MIPS_HI16`/`R_MIPS_LO16`
+    /// pair (accumulated until the matching `LO16` is seen, per the MIPS
+    /// ABI's requirement that `HI16` entries precede their `LO16`).
+    pub fn apply_relocations(&mut self, symtab: &ElfSection, sections: &[ElfSection]) -> Result<(), Error> {
+        let fmt = self.fmt;
+        let mut pending_hi16: Vec<u64> = Vec::new();
+
+        for &rel_idx in &self.relocated_by {
+            for reloc in &sections[rel_idx].relocations {
+                let symbol = symtab.symbols.get(reloc.sym_index as usize).ok_or_else(|| {
+                    Error::InvalidSection(format!("relocation references unknown symbol {}", reloc.sym_index))
+                })?;
+                let sym_base = sections.get(symbol.shndx32 as usize).map(|s| s.sh_addr).unwrap_or(0);
+                let s = symbol.st_value.wrapping_add(sym_base) as u32;
+                let off = reloc.r_offset as usize;
+                if off + 4 > self.data.len() {
+                    return Err(Error::InvalidSection(format!("relocation offset {:#x} out of bounds", off)));
+                }
+
+                match reloc.rel_type {
+                    R_MIPS_32 => {
+                        let a = reloc.r_addend.map(|a| a as u32).unwrap_or_else(|| fmt.unpack_u32(&self.data[off..off + 4]).unwrap_or(0));
+                        let value = s.wrapping_add(a);
+                        self.data[off..off + 4].copy_from_slice(&fmt.pack_u32(value));
+                    }
+                    R_MIPS_26 => {
+                        let instr = fmt.unpack_u32(&self.data[off..off + 4]).unwrap_or(0);
+                        let a = reloc.r_addend.map(|a| a as u32).unwrap_or(instr & 0x03ff_ffff);
+                        let target = s.wrapping_add(a << 2) >> 2 & 0x03ff_ffff;
+                        let patched = (instr & !0x03ff_ffff) | target;
+                        self.data[off..off + 4].copy_from_slice(&fmt.pack_u32(patched));
+                    }
+                    R_MIPS_HI16 => {
+                        pending_hi16.push(off as u64);
+                    }
+                    R_MIPS_LO16 => {
+                        let instr_lo = fmt.unpack_u32(&self.data[off..off + 4]).unwrap_or(0);
+                        let alo = (instr_lo & 0xffff) as i16 as i32;
+                        let ahi = if let Some(&hi_off) = pending_hi16.first() {
+                            let hi_instr = fmt.unpack_u32(&self.data[hi_off as usize..hi_off as usize + 4]).unwrap_or(0);
+                            (hi_instr & 0xffff) as i32
+                        } else {
+                            0
+                        };
+                        let ahl = (ahi << 16).wrapping_add(alo);
+                        let value = (s as i32).wrapping_add(ahl);
+
+                        for &hi_off in &pending_hi16 {
+                            let hi_instr = fmt.unpack_u32(&self.data[hi_off as usize..hi_off as usize + 4]).unwrap_or(0);
+                            let hi = ((value.wrapping_sub(value as i16 as i32)) >> 16) as u32 & 0xffff;
+                            let patched = (hi_instr & 0xffff_0000) | hi;
+                            self.data[hi_off as usize..hi_off as usize + 4].copy_from_slice(&fmt.pack_u32(patched));
+                        }
+                        pending_hi16.clear();
+
+                        let lo = value as u32 & 0xffff;
+                        let patched_lo = (instr_lo & 0xffff_0000) | lo;
+                        self.data[off..off + 4].copy_from_slice(&fmt.pack_u32(patched_lo));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub fn symbol_entries(&self) -> Result<&[Symbol], Error> {
         if self.sh_type != SHT_SYMTAB {
             return Err(Error::InvalidSection("Not a symbol table section".into()));
@@ -346,30 +644,20 @@ impl Default for ElfSection {
 }
 
 impl Section for ElfSection {
-    fn lookup_str(&self, index: usize) -> Result<String, Error> {
-        if self.sh_type != SHT_STRTAB {
-            return Err(Error::InvalidSection("Not a string table section".into()));
-        }
-
-        let end = self.data[index..]
+    fn lookup_str(&self, offset: u32) -> String {
+        let offset = offset as usize;
+        let end = self.data[offset..]
             .iter()
             .position(|&b| b == 0)
-            .ok_or_else(|| Error::InvalidSection("String not null-terminated".into()))?;
+            .map(|p| offset + p)
+            .unwrap_or(self.data.len());
 
         // Use latin1 encoding like Python
-        Ok(self.data[index..index + end].iter().map(|&b| b as char).collect())
+        self.data[offset..end].iter().map(|&b| b as char).collect()
     }
 
     fn add_str(&mut self, s: &str) -> Result<u32, Error> {
-        if self.sh_type != SHT_STRTAB {
-            return Err(Error::InvalidSection("Not a string table section".into()));
-        }
-        let ret = self.data.len() as u32;
-        // Convert to latin1 bytes like Python
-        self.data.extend(s.chars().map(|c| c as u8));
-        self.data.push(0);
-        self.sh_size = self.data.len() as u32;
-        Ok(ret)
+        ElfSection::add_str(self, s)
     }
 }
 
@@ -380,7 +668,7 @@ mod tests {
     #[test]
     fn test_section_header() {
         let data = {
-            let mut section = ElfSection::default();
+            let section = ElfSection::default();
             section.to_test_data()
         };
 
@@ -400,28 +688,39 @@ mod tests {
         assert_eq!(data, packed);
     }
 
+    #[test]
+    fn test_section_header_64() {
+        let fmt = ElfFormat::new_64(true);
+        let mut section = ElfSection { fmt, ..ElfSection::default() };
+        section.sh_name = 1;
+        section.sh_type = 2;
+        section.sh_flags = 3;
+        section.sh_addr = 4;
+        section.sh_offset = 5;
+        section.sh_size = 6;
+        section.sh_link = 7;
+        section.sh_info = 8;
+        section.sh_addralign = 9;
+        section.sh_entsize = 10;
+
+        let packed = section.to_bytes();
+        assert_eq!(packed.len(), 64);
+
+        let roundtripped = ElfSection::new(fmt, &packed).unwrap();
+        assert_eq!(roundtripped.sh_addr, 4);
+        assert_eq!(roundtripped.sh_size, 6);
+    }
+
     #[test]
     fn test_section_data() {
-        let fmt = ElfFormat { is_big_endian: false };
+        let fmt = ElfFormat::new(false);
         let mut section = ElfSection {
             fmt,
-            sh_name: 0,
-            sh_type: SHT_PROGBITS,
-            sh_flags: 0,
-            sh_addr: 0,
-            sh_offset: 0,
             sh_size: 5,
-            sh_link: 0,
-            sh_info: 0,
-            sh_addralign: 0,
-            sh_entsize: 0,
-            data: vec![65, 66, 67, 68, 69], // "ABCDE"
-            index: 0,
-            name: String::new(),
-            symbols: Vec::new(),
-            relocations: Vec::new(),
-            relocated_by: Vec::new(),
+            ..ElfSection::default()
         };
+        section.sh_type = SHT_PROGBITS;
+        section.data = vec![65, 66, 67, 68, 69]; // "ABCDE"
 
         // Test data access
         assert_eq!(section.data, [65, 66, 67, 68, 69]);
@@ -440,8 +739,8 @@ mod tests {
         let string_data = b"test\0string\0".to_vec();
         section.data = string_data;
 
-        assert_eq!(section.lookup_str(0).unwrap(), "test");
-        assert_eq!(section.lookup_str(5).unwrap(), "string");
+        assert_eq!(section.lookup_str(0), "test");
+        assert_eq!(section.lookup_str(5), "string");
     }
 
     #[test]
@@ -463,7 +762,7 @@ mod tests {
 
         assert_eq!(section.sh_name, 1);
         assert_eq!(section.sh_type, SHT_PROGBITS);
-        assert_eq!(section.sh_size, data.len() as u32);
+        assert_eq!(section.sh_size, data.len() as u64);
         assert_eq!(section.index, 5);
         assert_eq!(section.data, data);
     }
@@ -489,41 +788,27 @@ mod tests {
 
         assert_eq!(pos1, 0);
         assert_eq!(pos2, 6); // "test1\0" is 6 bytes
-        assert_eq!(section.lookup_str(0).unwrap(), "test1");
-        assert_eq!(section.lookup_str(6).unwrap(), "test2");
+        assert_eq!(section.lookup_str(0), "test1");
+        assert_eq!(section.lookup_str(6), "test2");
     }
 
     #[test]
     fn test_symbol_operations() {
-        let fmt = ElfFormat { is_big_endian: false };
-        let mut section = ElfSection {
+        let fmt = ElfFormat::new(false);
+        let strtab = ElfSection {
+            fmt,
+            sh_type: SHT_STRTAB,
+            data: b"\0local1\0".to_vec(),
+            ..ElfSection::default()
+        };
+        let symbol = Symbol::from_parts(fmt, 1, 2, 4, 0, 0, 1, &strtab, "local1".to_string()).unwrap();
+        let section = ElfSection {
             fmt,
-            sh_name: 0,
             sh_type: SHT_SYMTAB,
-            sh_flags: 0,
-            sh_addr: 0,
-            sh_offset: 0,
             sh_size: 16,
-            sh_link: 0,
-            sh_info: 0,
-            sh_addralign: 0,
             sh_entsize: 16,
-            data: vec![],
-            index: 0,
-            name: String::new(),
-            symbols: vec![
-                Symbol {
-                    st_name: 1,
-                    st_value: 2,
-                    st_size: 4,
-                    st_info: 0,
-                    st_other: 0,
-                    st_shndx: 1,
-                    name: "local1".to_string(),
-                }
-            ],
-            relocations: Vec::new(),
-            relocated_by: Vec::new(),
+            symbols: vec![symbol],
+            ..ElfSection::default()
         };
 
         let sections = vec![section.clone()];
@@ -532,102 +817,236 @@ mod tests {
 
     #[test]
     fn test_late_init() {
-        let fmt = ElfFormat { is_big_endian: false };
+        let fmt = ElfFormat::new(false);
         let mut sections = vec![
             ElfSection {
                 fmt,
-                sh_name: 0,
                 sh_type: SHT_PROGBITS,
-                sh_flags: 0,
-                sh_addr: 0,
-                sh_offset: 0,
-                sh_size: 0,
-                sh_link: 0,
-                sh_info: 0,
-                sh_addralign: 0,
-                sh_entsize: 0,
-                data: Vec::new(),
-                index: 0,
-                name: String::new(),
-                symbols: Vec::new(),
-                relocations: Vec::new(),
-                relocated_by: Vec::new(),
+                ..ElfSection::default()
             },
             ElfSection {
                 fmt,
-                sh_name: 0,
                 sh_type: SHT_REL,
-                sh_flags: 0,
-                sh_addr: 0,
-                sh_offset: 0,
-                sh_size: 0,
-                sh_link: 0,
-                sh_info: 0, // Points to section 0
-                sh_addralign: 0,
-                sh_entsize: 0,
-                data: Vec::new(),
                 index: 1,
-                name: String::new(),
-                symbols: Vec::new(),
-                relocations: Vec::new(),
-                relocated_by: Vec::new(),
+                sh_info: 0, // Points to section 0
+                ..ElfSection::default()
             },
         ];
-        
+
         // Initialize relocations
         let (target, rest) = sections.split_at_mut(1);
         rest[0].late_init(target).unwrap();
-        
+
         // Check that target section is marked as being relocated
         assert!(sections[0].relocated_by.contains(&1));
     }
 
+    #[test]
+    fn test_init_symbols_64() {
+        let fmt = ElfFormat::new_64(true);
+        let strtab = ElfSection {
+            fmt,
+            sh_type: SHT_STRTAB,
+            data: b"\0sym64\0".to_vec(),
+            ..ElfSection::default()
+        };
+        let entry = fmt.pack_symbol64(1, 0x1000, 0x20, 0x11, 0, 2);
+        let mut symtab = ElfSection {
+            fmt,
+            sh_type: SHT_SYMTAB,
+            data: entry,
+            ..ElfSection::default()
+        };
+
+        symtab.init_symbols(None).unwrap();
+
+        assert_eq!(symtab.symbols.len(), 1);
+        assert_eq!(symtab.symbols[0].st_value, 0x1000);
+        assert_eq!(symtab.symbols[0].st_size, 0x20);
+        assert_eq!(symtab.symbols[0].name, strtab.lookup_str(1));
+    }
+
+    #[test]
+    fn test_init_relocations_64() {
+        let fmt = ElfFormat::new_64(true);
+        let mut w = fmt.writer();
+        w.push_u64(0x10); // r_offset
+        w.push_u64(((5u64) << 32) | (R_MIPS_32 as u64)); // r_info: sym 5, type R_MIPS_32
+        w.push_u64(0); // r_addend
+        let mut section = ElfSection {
+            fmt,
+            sh_type: SHT_RELA,
+            data: w.into_bytes(),
+            ..ElfSection::default()
+        };
+
+        section.init_relocations().unwrap();
+
+        assert_eq!(section.relocations.len(), 1);
+        assert_eq!(section.relocations[0].sym_index, 5);
+        assert_eq!(section.relocations[0].rel_type, R_MIPS_32);
+    }
+
+    #[test]
+    fn test_compress_decompress_roundtrip() {
+        let fmt = ElfFormat::new(true);
+        let original = b"the quick brown fox jumps over the lazy dog".repeat(4);
+        let mut section = ElfSection {
+            fmt,
+            sh_type: SHT_PROGBITS,
+            sh_addralign: 4,
+            data: original.clone(),
+            ..ElfSection::default()
+        };
+
+        section.compress(ELFCOMPRESS_ZLIB).unwrap();
+        assert_ne!(section.sh_flags & SHF_COMPRESSED as u64, 0);
+        assert_eq!(section.data.len() as u64, section.sh_size);
+
+        section.decompress().unwrap();
+        assert_eq!(section.sh_flags & SHF_COMPRESSED as u64, 0);
+        assert_eq!(section.sh_addralign, 4);
+        assert_eq!(section.data, original);
+    }
+
+    #[test]
+    fn test_compress_decompress_roundtrip_64() {
+        let fmt = ElfFormat::new_64(true);
+        let original = b"the quick brown fox jumps over the lazy dog".repeat(4);
+        let mut section = ElfSection {
+            fmt,
+            sh_type: SHT_PROGBITS,
+            sh_addralign: 8,
+            data: original.clone(),
+            ..ElfSection::default()
+        };
+
+        section.compress(ELFCOMPRESS_ZLIB).unwrap();
+        section.decompress().unwrap();
+
+        assert_eq!(section.sh_addralign, 8);
+        assert_eq!(section.data, original);
+    }
+
+    #[test]
+    fn test_apply_relocations_mips32_and_hi_lo16() {
+        let fmt = ElfFormat::new(true);
+
+        let strtab = ElfSection { fmt, sh_type: SHT_STRTAB, data: b"\0target\0".to_vec(), ..ElfSection::default() };
+        let symbol = Symbol::from_parts(fmt, 1, 0x1234_5678, 0, 0, 0, 0, &strtab, "target".to_string()).unwrap();
+        let symtab = ElfSection { fmt, sh_type: SHT_SYMTAB, symbols: vec![symbol], ..ElfSection::default() };
+
+        // R_MIPS_32 at offset 0 (word starts at 0, addend baked into the word).
+        // R_MIPS_HI16 at offset 4, R_MIPS_LO16 at offset 8 (lui/addiu pair with a zero immediate).
+        let mut data = vec![0u8; 12];
+        data[0..4].copy_from_slice(&fmt.pack_u32(0)); // R_MIPS_32 target word (A = 0)
+        data[4..8].copy_from_slice(&fmt.pack_u32(0x3c04_0000)); // lui $a0, 0 (HI16)
+        data[8..12].copy_from_slice(&fmt.pack_u32(0x2484_0000)); // addiu $a0, $a0, 0 (LO16)
+
+        let rel_section = ElfSection {
+            fmt,
+            sh_type: SHT_REL,
+            index: 1,
+            relocations: vec![
+                Relocation::new(fmt, &{
+                    let mut r = fmt.writer();
+                    r.push_u32(0);
+                    r.push_u32((0u32 << 8) | R_MIPS_32);
+                    r.into_bytes()
+                }, SHT_REL),
+                Relocation::new(fmt, &{
+                    let mut r = fmt.writer();
+                    r.push_u32(4);
+                    r.push_u32((0u32 << 8) | R_MIPS_HI16);
+                    r.into_bytes()
+                }, SHT_REL),
+                Relocation::new(fmt, &{
+                    let mut r = fmt.writer();
+                    r.push_u32(8);
+                    r.push_u32((0u32 << 8) | R_MIPS_LO16);
+                    r.into_bytes()
+                }, SHT_REL),
+            ],
+            ..ElfSection::default()
+        };
+
+        let mut section = ElfSection { fmt, sh_type: SHT_PROGBITS, data, relocated_by: vec![1], ..ElfSection::default() };
+        let sections = vec![section.clone(), rel_section];
+
+        section.apply_relocations(&symtab, &sections).unwrap();
+
+        assert_eq!(fmt.unpack_u32(&section.data[0..4]).unwrap(), 0x1234_5678);
+        assert_eq!(fmt.unpack_u32(&section.data[4..8]).unwrap() & 0xffff, 0x1234);
+        assert_eq!(fmt.unpack_u32(&section.data[8..12]).unwrap() & 0xffff, 0x5678);
+    }
+
     #[test]
     fn test_mdebug_relocation() {
         let fmt = ElfFormat::new(true);
         let mut section = ElfSection {
             fmt,
-            sh_name: 0,
             sh_type: SHT_MIPS_DEBUG,
-            sh_flags: 0,
-            sh_addr: 0,
             sh_offset: 0x2000,  // Set offset to 0x2000
-            sh_size: 0,
-            sh_link: 0,
-            sh_info: 0,
-            sh_addralign: 0,
-            sh_entsize: 0,
             data: {
                 let mut data = vec![0; 0x60];  // Initialize with enough space
-                
+
                 // Pack the magic value (0x7009) and version stamp (1)
-                fmt.pack_u16(&mut data[0..2], 0x7009).unwrap();
-                fmt.pack_u16(&mut data[2..4], 1).unwrap();
-                
-                // Initialize all values to 0
-                let mut values = vec![0u32; 23];
-                
+                data[0..2].copy_from_slice(&fmt.pack_u16(0x7009));
+                data[2..4].copy_from_slice(&fmt.pack_u16(1));
+
                 // Set test values: ilineMax = 1, cbLineOffset = 0x10
-                values[0] = 1;  // ilineMax
-                values[2] = 0x10;  // cbLineOffset
-                
-                // Pack the values
-                fmt.pack_tuple_u32(&mut data[4..0x60], &values).unwrap();
-                
+                data[4..8].copy_from_slice(&fmt.pack_u32(1)); // ilineMax
+                data[12..16].copy_from_slice(&fmt.pack_u32(0x10)); // cbLineOffset
+
                 data
             },
-            index: 0,
-            name: String::new(),
-            symbols: Vec::new(),
-            relocations: Vec::new(),
-            relocated_by: Vec::new(),
+            ..ElfSection::default()
         };
 
         // Apply relocation
         section.relocate_mdebug(0x1000).unwrap();
 
         // Check that the offset was updated correctly
-        let values = section.fmt.unpack_tuple_u32(&section.data[4..0x60], 23).unwrap();
-        assert_eq!(values[2], 0x1010);  // 0x10 + (0x2000 - 0x1000)
+        let cb_line_offset = section.fmt.unpack_u32(&section.data[12..16]).unwrap();
+        assert_eq!(cb_line_offset, 0x1010);  // 0x10 + (0x2000 - 0x1000)
+    }
+
+    #[test]
+    fn test_relocate_debug_line() {
+        let fmt = ElfFormat::new(true);
+
+        // One 32-bit DWARF unit: unit_length, version(2), header_length(4),
+        // header bytes (none, header_length = 0), then a DW_LNE_set_address
+        // extended opcode (0x00, uleb len = 5, sub-opcode 0x02, 4-byte addr).
+        let mut program = Vec::new();
+        program.push(0x00); // extended opcode marker
+        program.push(0x05); // ULEB128 length = 5 (sub-opcode + 4-byte address)
+        program.push(0x02); // DW_LNE_set_address
+        program.extend_from_slice(&fmt.pack_u32(0x8000));
+
+        let mut unit_body = Vec::new();
+        unit_body.extend_from_slice(&fmt.pack_u16(4)); // version
+        unit_body.extend_from_slice(&fmt.pack_u32(0)); // header_length
+        unit_body.extend_from_slice(&program);
+
+        let mut data = Vec::new();
+        data.extend_from_slice(&fmt.pack_u32(unit_body.len() as u32)); // unit_length
+        data.extend_from_slice(&unit_body);
+
+        let mut section = ElfSection {
+            fmt,
+            sh_type: SHT_PROGBITS,
+            sh_offset: 0x2000,
+            name: ".debug_line".to_string(),
+            data,
+            ..ElfSection::default()
+        };
+
+        section.relocate_debug(0x1000).unwrap();
+
+        // unit_length(4) + version(2) + header_length(4) + extended-opcode prefix(3).
+        let addr_off = 4 + 2 + 4 + 3;
+        let addr = fmt.unpack_u32(&section.data[addr_off..addr_off + 4]).unwrap();
+        assert_eq!(addr, 0x9000); // 0x8000 + (0x2000 - 0x1000)
     }
 }
@@ -1,5 +1,5 @@
 use crate::elf::format::ElfFormat;
-use crate::elf::constants::SHN_XINDEX;
+use crate::elf::constants::{SHN_XINDEX, SHN_LORESERVE, SHN_ABS, SHN_COMMON};
 use crate::elf::section::Section;
 use thiserror::Error;
 
@@ -7,27 +7,36 @@ use thiserror::Error;
 pub enum SymbolError {
     #[error("SHN_XINDEX not supported (too many sections)")]
     XindexNotSupported,
+    #[error("invalid symbol data: {0}")]
+    InvalidData(String),
 }
 
 /// Represents an ELF symbol table entry
-/// 
+///
+/// `st_value`/`st_size` are stored widened to `u64` so the same struct
+/// covers both layouts:
+///
 /// ```c
-/// typedef struct {
-///     Elf32_Word      st_name;
-///     Elf32_Addr      st_value;
-///     Elf32_Word      st_size;
-///     unsigned char   st_info;
-///     unsigned char   st_other;
-///     Elf32_Half      st_shndx;
-/// } Elf32_Sym;
+/// typedef struct {            typedef struct {
+///     Elf32_Word    st_name;      Elf64_Word    st_name;
+///     Elf32_Addr    st_value;     unsigned char st_info;
+///     Elf32_Word    st_size;      unsigned char st_other;
+///     unsigned char st_info;      Elf64_Half    st_shndx;
+///     unsigned char st_other;     Elf64_Addr    st_value;
+///     Elf32_Half    st_shndx;     Elf64_Xword   st_size;
+/// } Elf32_Sym;                } Elf64_Sym;
 /// ```
 #[derive(Debug, Clone)]
 pub struct Symbol {
     pub st_name: u32,
-    pub st_value: u32,
-    pub st_size: u32,
+    pub st_value: u64,
+    pub st_size: u64,
     pub st_other: u8,
     pub st_shndx: u16,
+    /// Resolved section index. Equal to `st_shndx` unless the symbol used
+    /// `SHN_XINDEX`, in which case this holds the real index read from the
+    /// parallel `SHT_SYMTAB_SHNDX` table.
+    pub shndx32: u32,
     pub bind: u8,
     pub type_: u8,
     pub visibility: u8,
@@ -36,12 +45,23 @@ pub struct Symbol {
 }
 
 impl Symbol {
-    pub fn new<S: Section>(fmt: ElfFormat, data: &[u8], strtab: &S, name: Option<String>) -> Result<Self, SymbolError> {
-        let (st_name, st_value, st_size, st_info, st_other, st_shndx) = fmt.unpack_symbol(data);
+    /// Parse a symbol table entry.
+    ///
+    /// `xshndx` is the corresponding entry from the `SHT_SYMTAB_SHNDX`
+    /// section (if one is present), used to resolve `st_shndx ==
+    /// SHN_XINDEX`.
+    pub fn new<S: Section>(
+        fmt: ElfFormat,
+        data: &[u8],
+        strtab: &S,
+        name: Option<String>,
+        xshndx: Option<u32>,
+    ) -> Result<Self, SymbolError> {
+        let (st_name, st_value, st_size, st_info, st_other, st_shndx) = fmt
+            .unpack_symbol64(data)
+            .map_err(|e| SymbolError::InvalidData(e.to_string()))?;
 
-        if st_shndx == SHN_XINDEX {
-            return Err(SymbolError::XindexNotSupported);
-        }
+        let shndx32 = Self::resolve_shndx(st_shndx, xshndx)?;
 
         let bind = st_info >> 4;
         let type_ = st_info & 15;
@@ -53,6 +73,7 @@ impl Symbol {
             st_size,
             st_other,
             st_shndx,
+            shndx32,
             bind,
             type_,
             visibility,
@@ -64,17 +85,15 @@ impl Symbol {
     pub fn from_parts<S: Section>(
         fmt: ElfFormat,
         st_name: u32,
-        st_value: u32,
-        st_size: u32,
+        st_value: u64,
+        st_size: u64,
         st_info: u8,
         st_other: u8,
         st_shndx: u16,
         strtab: &S,
         name: String,
     ) -> Result<Self, SymbolError> {
-        if st_shndx == SHN_XINDEX {
-            return Err(SymbolError::XindexNotSupported);
-        }
+        let shndx32 = Self::resolve_shndx(st_shndx, None)?;
 
         let bind = st_info >> 4;
         let type_ = st_info & 15;
@@ -86,6 +105,7 @@ impl Symbol {
             st_size,
             st_other,
             st_shndx,
+            shndx32,
             bind,
             type_,
             visibility,
@@ -94,16 +114,63 @@ impl Symbol {
         })
     }
 
+    /// Resolve the real section index, following `SHN_XINDEX` into the
+    /// parallel extended-index table when present.
+    fn resolve_shndx(st_shndx: u16, xshndx: Option<u32>) -> Result<u32, SymbolError> {
+        if st_shndx == SHN_XINDEX {
+            xshndx.map(|v| v as u32).ok_or(SymbolError::XindexNotSupported)
+        } else {
+            Ok(st_shndx as u32)
+        }
+    }
+
+    /// Whether this symbol needs an `SHT_SYMTAB_SHNDX` entry to round-trip
+    /// (its resolved section index doesn't fit in the 16-bit `st_shndx`).
+    /// `SHN_ABS`/`SHN_COMMON` lie in the reserved range too, but they're
+    /// pseudo-section markers rather than real section numbers, so they're
+    /// excluded - they always fit in `st_shndx` as-is.
+    pub fn needs_xindex(&self) -> bool {
+        self.shndx32 >= SHN_LORESERVE as u32
+            && self.shndx32 != SHN_ABS as u32
+            && self.shndx32 != SHN_COMMON as u32
+    }
+
+    /// Point this symbol at a different section, e.g. after the section
+    /// list has been reordered. Keeps `st_shndx`/`shndx32` consistent so
+    /// [`Self::needs_xindex`]/[`Self::to_bytes`] still agree on whether an
+    /// `SHT_SYMTAB_SHNDX` entry is required.
+    pub fn set_shndx(&mut self, shndx: u32) {
+        self.shndx32 = shndx;
+        self.st_shndx = if shndx >= SHN_LORESERVE as u32 {
+            SHN_XINDEX
+        } else {
+            shndx as u16
+        };
+    }
+
+    /// Point this symbol at a different name, e.g. after a new string has
+    /// been appended to its string table. `st_name` is the byte offset the
+    /// new string was written at; `name` is kept in sync so later lookups
+    /// (`find_symbol`, diagnostics) don't need to re-resolve it.
+    pub fn set_name(&mut self, st_name: u32, name: String) {
+        self.st_name = st_name;
+        self.name = name;
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         let st_info = (self.bind << 4) | self.type_;
-        let mut result = Vec::with_capacity(16);
-        result.extend_from_slice(&self.fmt.pack_u32(self.st_name));
-        result.extend_from_slice(&self.fmt.pack_u32(self.st_value));
-        result.extend_from_slice(&self.fmt.pack_u32(self.st_size));
-        result.push(st_info);
-        result.push(self.st_other);
-        result.extend_from_slice(&self.fmt.pack_u16(self.st_shndx));
-        result
+        let st_shndx = if self.needs_xindex() {
+            SHN_XINDEX
+        } else {
+            self.st_shndx
+        };
+        self.fmt.pack_symbol64(self.st_name, self.st_value, self.st_size, st_info, self.st_other, st_shndx)
+    }
+
+    /// The value to store in this symbol's `SHT_SYMTAB_SHNDX` slot, whether
+    /// or not the symbol itself ends up using `SHN_XINDEX`.
+    pub fn xindex_entry(&self) -> u32 {
+        self.shndx32
     }
 }
 
@@ -130,7 +197,7 @@ mod tests {
         data.extend_from_slice(&fmt.pack_u16(1)); // st_shndx
 
         let strtab = MockSection;
-        let sym = Symbol::new(fmt, &data, &strtab, None).unwrap();
+        let sym = Symbol::new(fmt, &data, &strtab, None, None).unwrap();
 
         assert_eq!(sym.st_name, 1);
         assert_eq!(sym.st_value, 0x1000);
@@ -139,6 +206,7 @@ mod tests {
         assert_eq!(sym.type_, 2);
         assert_eq!(sym.visibility, 3);
         assert_eq!(sym.st_shndx, 1);
+        assert_eq!(sym.shndx32, 1);
         assert_eq!(sym.name, "mock_symbol");
     }
 
@@ -154,13 +222,13 @@ mod tests {
         data.extend_from_slice(&fmt.pack_u16(1)); // st_shndx
 
         let strtab = MockSection;
-        let sym = Symbol::new(fmt, &data, &strtab, None).unwrap();
+        let sym = Symbol::new(fmt, &data, &strtab, None, None).unwrap();
         let bytes = sym.to_bytes();
         assert_eq!(data, bytes);
     }
 
     #[test]
-    fn test_symbol_xindex_error() {
+    fn test_symbol_xindex_without_table_errors() {
         let fmt = ElfFormat::new(true);
         let mut data = Vec::new();
         data.extend_from_slice(&fmt.pack_u32(1)); // st_name
@@ -172,8 +240,65 @@ mod tests {
 
         let strtab = MockSection;
         assert!(matches!(
-            Symbol::new(fmt, &data, &strtab, None),
+            Symbol::new(fmt, &data, &strtab, None, None),
             Err(SymbolError::XindexNotSupported)
         ));
     }
+
+    #[test]
+    fn test_symbol_xindex_resolved_from_table() {
+        let fmt = ElfFormat::new(true);
+        let mut data = Vec::new();
+        data.extend_from_slice(&fmt.pack_u32(1)); // st_name
+        data.extend_from_slice(&fmt.pack_u32(0x1000)); // st_value
+        data.extend_from_slice(&fmt.pack_u32(32)); // st_size
+        data.push(0x12); // st_info
+        data.push(0x3); // st_other
+        data.extend_from_slice(&fmt.pack_u16(SHN_XINDEX)); // st_shndx = SHN_XINDEX
+
+        let strtab = MockSection;
+        let sym = Symbol::new(fmt, &data, &strtab, None, Some(0x1_0002)).unwrap();
+        assert_eq!(sym.st_shndx, SHN_XINDEX);
+        assert_eq!(sym.shndx32, 0x1_0002);
+        assert!(sym.needs_xindex());
+
+        let bytes = sym.to_bytes();
+        let (.., st_shndx) = fmt.unpack_symbol(&bytes).unwrap();
+        assert_eq!(st_shndx, SHN_XINDEX);
+    }
+
+    #[test]
+    fn test_symbol_abs_does_not_need_xindex() {
+        let fmt = ElfFormat::new(true);
+        let mut data = Vec::new();
+        data.extend_from_slice(&fmt.pack_u32(1)); // st_name
+        data.extend_from_slice(&fmt.pack_u32(0x1000)); // st_value
+        data.extend_from_slice(&fmt.pack_u32(32)); // st_size
+        data.push(0x12); // st_info
+        data.push(0x3); // st_other
+        data.extend_from_slice(&fmt.pack_u16(SHN_ABS)); // st_shndx = SHN_ABS
+
+        let strtab = MockSection;
+        let sym = Symbol::new(fmt, &data, &strtab, None, None).unwrap();
+        assert_eq!(sym.shndx32, SHN_ABS as u32);
+        assert!(!sym.needs_xindex());
+
+        let bytes = sym.to_bytes();
+        assert_eq!(data, bytes);
+    }
+
+    #[test]
+    fn test_symbol64_parse_and_roundtrip() {
+        let fmt = ElfFormat::new_64(true);
+        let data = fmt.pack_symbol64(1, 0x1_0000_0000, 0x40, 0x12, 0x3, 1);
+
+        let strtab = MockSection;
+        let sym = Symbol::new(fmt, &data, &strtab, None, None).unwrap();
+        assert_eq!(sym.st_value, 0x1_0000_0000);
+        assert_eq!(sym.st_size, 0x40);
+        assert_eq!(sym.bind, 1);
+        assert_eq!(sym.type_, 2);
+
+        assert_eq!(sym.to_bytes(), data);
+    }
 }
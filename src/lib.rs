@@ -1,3 +1,6 @@
+pub mod ar;
+pub mod arch;
+pub mod coff;
 pub mod elf;
 pub mod objfile;
 pub mod utils;
@@ -6,9 +9,10 @@ pub mod processor;
 use std::io::Write;
 use std::path::Path;
 use clap::{Arg, Command, ArgAction, ArgGroup, value_parser};
-use crate::objfile::{fixup_objfile, AsmFunction};
+use crate::objfile::{fixup_archive, fixup_objfile, AsmFunction};
 use crate::processor::parse_source;
 use crate::utils::{Error, Opts};
+use crate::utils::cli::merge_env_opts;
 
 /// Main entry point for the asm-processor
 ///
@@ -62,15 +66,49 @@ pub fn run(
                 .map_err(|e| Error::Io(e))?;
         }
 
-        fixup_objfile(
-            args.objfile.unwrap(),
-            &functions,
-            &asm_prelude,
-            args.assembler.unwrap(),
-            args.output_enc,
-            args.drop_mdebug_gptab,
-            args.convert_statics,
-        )?;
+        let objfile = args.objfile.unwrap();
+        let assembler = args.assembler.unwrap();
+        let profile = crate::arch::from_name(&opts.arch)?;
+        let symbol_overrides = std::collections::HashMap::new();
+        // This legacy entry point has no `--symbols`/`--symbol-map`/
+        // `--drop-comment`/`--split-meta` flags of its own (see
+        // `src/bin/asm_processor.rs` for the CLI that does), so it always
+        // runs fixup with those features off.
+        let symbol_map = std::collections::HashMap::new();
+
+        // A `.a` archive bundles multiple object files; post-process every
+        // ELF member in place instead of treating the path as a lone `.o`.
+        if objfile.ends_with(".a") {
+            fixup_archive(
+                Path::new(&objfile),
+                &functions,
+                &asm_prelude,
+                &assembler,
+                &args.output_enc,
+                args.drop_mdebug_gptab,
+                false,
+                &args.convert_statics,
+                &symbol_overrides,
+                &symbol_map,
+                profile.as_ref(),
+                false,
+            )?;
+        } else {
+            fixup_objfile(
+                Path::new(&objfile),
+                &functions,
+                &asm_prelude,
+                &assembler,
+                &args.output_enc,
+                args.drop_mdebug_gptab,
+                false,
+                &args.convert_statics,
+                &symbol_overrides,
+                &symbol_map,
+                profile.as_ref(),
+                false,
+            )?;
+        }
 
         Ok((Vec::new(), Vec::new()))
     }
@@ -78,6 +116,7 @@ pub fn run(
 
 /// Parse command line arguments
 fn parse_args(argv: &[String]) -> Result<Args, Error> {
+    let argv = merge_env_opts(argv);
     let matches = Command::new("asm-processor")
         .about("Pre-process .c files and post-process .o files to enable embedding assembly into C.")
         .arg(Arg::new("filename")
@@ -145,7 +184,7 @@ fn parse_args(argv: &[String]) -> Result<Args, Error> {
         .group(ArgGroup::new("opt")
             .args(["O0", "O1", "O2", "g"])
             .required(true))
-        .try_get_matches_from(argv)
+        .try_get_matches_from(&argv)
         .map_err(|e| Error::InvalidInput(e.to_string()))?;
 
     let opt = if matches.get_flag("O0") {
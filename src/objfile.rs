@@ -9,17 +9,163 @@ use crate::elf::{
         MIPS_DEBUG_ST_STATIC, MIPS_DEBUG_ST_STATIC_PROC, MIPS_DEBUG_ST_FILE,
         MIPS_DEBUG_ST_STRUCT, MIPS_DEBUG_ST_UNION, MIPS_DEBUG_ST_ENUM,
         MIPS_DEBUG_ST_BLOCK, MIPS_DEBUG_ST_PROC, MIPS_DEBUG_ST_END,
-        STT_FUNC, STT_OBJECT, STB_LOCAL, STB_GLOBAL, STV_DEFAULT,
-        SHN_UNDEF, SHT_REL, SHT_RELA
+        STT_FUNC, STT_OBJECT, STB_LOCAL, STB_GLOBAL, STB_WEAK, STV_DEFAULT,
+        SHN_UNDEF, SHT_REL, SHT_RELA, SHT_NOTE
     }
 };
 
+use crate::elf::builder::ElfBuilder;
 use crate::elf::file::ElfFile;
 use crate::utils::Error as CrateError;
 use crate::asm::Function;
+use crate::arch::Arch;
 
 const SECTIONS: &[&str] = &[".data", ".text", ".rodata", ".bss"];
 
+/// Per-symbol overrides read from a `--symbols` control file, used to make
+/// fine-grained decisions that the blanket `convert_statics` mode can't
+/// express: keep this static local, promote that one to global, force-keep
+/// an otherwise-unreferenced symbol, or set its `st_size`. Overrides are
+/// applied only to the statics recovered from `.mdebug`; `align` is parsed
+/// but not applied to any symbol.
+#[derive(Debug, Clone, Default)]
+pub struct SymbolOverride {
+    pub visibility: Option<String>,
+    pub force_active: bool,
+    pub size: Option<u64>,
+    pub align: Option<u32>,
+}
+
+/// Parse a `--symbols` control file.
+///
+/// The format is one entry per symbol:
+///
+/// ```text
+/// my_static_var: visibility=local
+/// g_someTable: visibility=global force_active=true size=64 align=8
+/// ```
+///
+/// Blank lines and lines starting with `#` are ignored.
+pub fn parse_symbol_overrides(path: &Path) -> Result<HashMap<String, SymbolOverride>> {
+    let contents = std::fs::read_to_string(path)?;
+    let mut overrides = HashMap::new();
+
+    for (line_no, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (name, fields) = line.split_once(':').ok_or_else(|| {
+            ObjFileError::SymbolError(format!(
+                "{}:{}: expected \"name: field=value ...\"",
+                path.display(),
+                line_no + 1
+            ))
+        })?;
+
+        let mut entry = SymbolOverride::default();
+        for field in fields.split_whitespace() {
+            let (key, value) = field.split_once('=').ok_or_else(|| {
+                ObjFileError::SymbolError(format!(
+                    "{}:{}: expected key=value, got \"{}\"",
+                    path.display(),
+                    line_no + 1,
+                    field
+                ))
+            })?;
+            match key {
+                "visibility" => entry.visibility = Some(value.to_string()),
+                "force_active" => entry.force_active = value.parse().unwrap_or(true),
+                "size" => entry.size = value.parse().ok(),
+                "align" => entry.align = value.parse().ok(),
+                _ => return Err(ObjFileError::SymbolError(format!(
+                    "{}:{}: unknown symbol-control field \"{}\"",
+                    path.display(),
+                    line_no + 1,
+                    key
+                ))),
+            }
+        }
+        overrides.insert(name.trim().to_string(), entry);
+    }
+
+    Ok(overrides)
+}
+
+/// Parse a GNU ld `-Map` file's Cross Reference Table (the symbol/file
+/// listing `ld` appends when invoked with `--cref`) into a `name -> ELF
+/// binding` table, for `--convert-statics from-map`.
+///
+/// The table looks like:
+///
+/// ```text
+/// Cross Reference Table
+///
+/// Symbol                                            File
+/// g_someStatic                                       a.c.o
+/// g_someShared                                        a.c.o
+///                                                      b.c.o
+/// ```
+///
+/// A symbol referenced from only the object file that defines it (like
+/// `g_someStatic` above) resolves to `STB_LOCAL`; one referenced from more
+/// than one object file (`g_someShared`, pulled in by `b.c.o` too) resolves
+/// to `STB_GLOBAL` -- the same call a human reviewing the map by hand would
+/// make. Metrowerks CodeWarrior maps (used by GC/Wii decomp projects) embed
+/// an equivalent table under the same heading and parse the same way.
+pub fn parse_linker_map(path: &Path) -> Result<HashMap<String, u8>> {
+    let contents = std::fs::read_to_string(path)?;
+    let mut bindings = HashMap::new();
+
+    let mut in_cref = false;
+    let mut seen_entry = false;
+    let mut current: Option<(String, usize)> = None;
+    for line in contents.lines() {
+        if line.trim() == "Cross Reference Table" {
+            in_cref = true;
+            continue;
+        }
+        if !in_cref {
+            continue;
+        }
+        if line.trim().is_empty() {
+            // The blank line right after the "Cross Reference Table"
+            // heading (and, in some generators, before the "Symbol File"
+            // header) doesn't end the table -- only the blank line that
+            // follows the last entry does.
+            if seen_entry {
+                break;
+            }
+            continue;
+        }
+        if line.trim_start().starts_with("Symbol") {
+            continue;
+        }
+
+        if line.starts_with(char::is_whitespace) {
+            // A continuation line: another file referencing the symbol
+            // from the last non-indented line.
+            if let Some((_, count)) = &mut current {
+                *count += 1;
+            }
+            continue;
+        }
+
+        if let Some((name, count)) = current.take() {
+            bindings.insert(name, if count > 1 { STB_GLOBAL } else { STB_LOCAL });
+        }
+        let name = line.split_whitespace().next().unwrap_or("").to_string();
+        current = Some((name, 1));
+        seen_entry = true;
+    }
+    if let Some((name, count)) = current.take() {
+        bindings.insert(name, if count > 1 { STB_GLOBAL } else { STB_LOCAL });
+    }
+
+    Ok(bindings)
+}
+
 /// Error type for object file processing operations
 #[derive(Debug, thiserror::Error)]
 pub enum ObjFileError {
@@ -104,7 +250,18 @@ impl PrevLocs {
 /// * `assembler` - Assembler command to use
 /// * `output_enc` - Output encoding
 /// * `drop_mdebug_gptab` - Whether to drop mdebug and gptab sections
+/// * `drop_comment` - Whether to drop the `.comment` section entirely
+///   instead of merging it, for builds that want byte-identical output
+///   without compiler version strings
 /// * `convert_statics` - How to handle static symbols
+/// * `symbol_overrides` - Per-symbol overrides from a `--symbols` control
+///   file; entries here take precedence over the blanket `convert_statics`
+///   mode for matching names
+/// * `symbol_map` - Name-to-binding table parsed by [`parse_linker_map`],
+///   consulted when `convert_statics` is `"from-map"`
+/// * `arch` - Target architecture profile; used here to validate that every
+///   relocation in the merged object file is one the `--arch` profile
+///   recognizes
 ///
 /// # Returns
 /// * `Result<(), ObjFileError>` - Success or error
@@ -115,10 +272,95 @@ pub fn fixup_objfile(
     assembler: &str,
     output_enc: &str,
     drop_mdebug_gptab: bool,
+    drop_comment: bool,
     convert_statics: &str,
+    symbol_overrides: &HashMap<String, SymbolOverride>,
+    symbol_map: &HashMap<String, u8>,
+    arch: &dyn Arch,
+    split_meta: bool,
 ) -> Result<()> {
-    // Read the object file
-    let mut objfile = ElfFile::from_file(objfile_path)?;
+    let objfile = ElfFile::from_file(objfile_path)?;
+    let mut builder = fixup_objfile_data(
+        objfile,
+        functions,
+        asm_prelude,
+        assembler,
+        output_enc,
+        drop_mdebug_gptab,
+        drop_comment,
+        convert_statics,
+        symbol_overrides,
+        symbol_map,
+        arch,
+        split_meta,
+    )?;
+    builder.write(objfile_path.to_str()
+        .ok_or_else(|| ObjFileError::Io(io::Error::new(io::ErrorKind::Other, "Invalid output path")))?)
+        .map_err(ObjFileError::from)?;
+    Ok(())
+}
+
+/// As [`fixup_objfile`], but for callers embedding this crate as a library
+/// that keep build artifacts in memory instead of on disk: takes the
+/// assembled `.o` contents directly and returns the fixed-up object bytes,
+/// without ever touching the filesystem for the object file itself (the
+/// external assembler invocation still goes through a temp file, since
+/// that's dictated by the assembler's own command-line interface).
+pub fn fixup_objfile_bytes(
+    object: &[u8],
+    functions: &[Function],
+    asm_prelude: &[u8],
+    assembler: &str,
+    output_enc: &str,
+    drop_mdebug_gptab: bool,
+    drop_comment: bool,
+    convert_statics: &str,
+    symbol_overrides: &HashMap<String, SymbolOverride>,
+    symbol_map: &HashMap<String, u8>,
+    arch: &dyn Arch,
+    split_meta: bool,
+) -> Result<Vec<u8>> {
+    let objfile = ElfFile::new(object)?;
+    let mut builder = fixup_objfile_data(
+        objfile,
+        functions,
+        asm_prelude,
+        assembler,
+        output_enc,
+        drop_mdebug_gptab,
+        drop_comment,
+        convert_statics,
+        symbol_overrides,
+        symbol_map,
+        arch,
+        split_meta,
+    )?;
+    builder.write_to_vec().map_err(ObjFileError::from)
+}
+
+/// The member-level fixup pipeline shared by [`fixup_objfile`] (a single
+/// `.o` on disk) and [`fixup_archive`] (each ELF member of a `.a`, entirely
+/// in memory): runs the section/symbol/relocation rewriting against an
+/// already-parsed [`ElfFile`], then hands the result back wrapped in an
+/// [`ElfBuilder`] so the caller's final write recomputes section header
+/// offsets, `sh_link`/`sh_info` and alignment instead of assuming the
+/// rewritten sections still fit the original layout. Doesn't read or write
+/// the object file on disk itself; only the external assembler run goes
+/// through temporary files.
+fn fixup_objfile_data(
+    mut objfile: ElfFile,
+    functions: &[Function],
+    asm_prelude: &[u8],
+    assembler: &str,
+    output_enc: &str,
+    drop_mdebug_gptab: bool,
+    drop_comment: bool,
+    convert_statics: &str,
+    symbol_overrides: &HashMap<String, SymbolOverride>,
+    symbol_map: &HashMap<String, u8>,
+    arch: &dyn Arch,
+    split_meta: bool,
+) -> Result<ElfBuilder> {
     let fmt = objfile.fmt.clone();
 
     let mut prev_locs = PrevLocs::default();
@@ -163,7 +405,7 @@ pub fn fixup_objfile(
                     asm.push(format!(".section {}", sectype));
                     if sectype == ".text" {
                         for _ in 0..((loc.1 - prev_loc) / 4) {
-                            asm.push("nop".to_string());
+                            asm.push(arch.text_pad_mnemonic().to_string());
                         }
                     } else {
                         asm.push(format!(".space {}", loc.1 - prev_loc));
@@ -318,14 +560,34 @@ pub fn fixup_objfile(
     // Process sections
     process_sections(&mut objfile, &to_copy, &all_text_glabels)?;
     
-    // Handle reginfo section merging
-    if let Some(target_reginfo) = objfile.find_section(".reginfo") {
-        if let Some(source_reginfo) = asm_objfile.find_section(".reginfo") {
-            let mut data = target_reginfo.data.clone();
-            for i in 0..20 {
-                data[i] |= source_reginfo.data[i];
+    // Handle reginfo section merging (MIPS o32 only; PowerPC EABI has no
+    // `.reginfo` section to merge)
+    if arch.has_reginfo() {
+        if let Some(target_reginfo) = objfile.find_section(".reginfo") {
+            if let Some(source_reginfo) = asm_objfile.find_section(".reginfo") {
+                let mut data = target_reginfo.data.clone();
+                for i in 0..20 {
+                    data[i] |= source_reginfo.data[i];
+                }
+                target_reginfo.data = data;
+            }
+        }
+    }
+
+    // Handle the compiler `.comment` section: merge the assembled temp
+    // object's producer string into the C object's instead of letting the
+    // two collide, or drop it outright for byte-identical output that
+    // shouldn't carry toolchain version strings.
+    if drop_comment {
+        objfile.sections.retain(|s| s.name != ".comment");
+    } else if let Some(target_comment) = objfile.find_section(".comment") {
+        if let Some(source_comment) = asm_objfile.find_section(".comment") {
+            let mut data = target_comment.data.clone();
+            if data.last() != Some(&0) {
+                data.push(0);
             }
-            target_reginfo.data = data;
+            data.extend_from_slice(&source_comment.data);
+            target_comment.data = data;
         }
     }
 
@@ -335,14 +597,147 @@ pub fn fixup_objfile(
     }
 
     // Process symbols and relocations
-    process_symbols(&mut objfile, convert_statics, &all_text_glabels, &relocated_symbols, &func_sizes, &moved_late_rodata)?;
-    process_relocations(&mut objfile, &modified_text_positions, &jtbl_rodata_positions, &moved_late_rodata)?;
+    process_symbols(&mut objfile, convert_statics, symbol_overrides, symbol_map, &all_text_glabels, &relocated_symbols, &func_sizes, &moved_late_rodata, arch)?;
+    process_relocations(&mut objfile, &modified_text_positions, &jtbl_rodata_positions, &moved_late_rodata, arch)?;
 
-    // Write back the modified object file
-    objfile.write(objfile_path.to_str()
-        .ok_or_else(|| ObjFileError::Io(io::Error::new(io::ErrorKind::Other, "Invalid output path")))?)
-        .map_err(|e| ObjFileError::from(e))?;
+    if split_meta {
+        write_split_meta(&mut objfile, &func_sizes, &to_copy)?;
+    }
+
+    Ok(ElfBuilder::from_file(objfile))
+}
+
+/// Like [`fixup_objfile`], but for a static library (`.a`) instead of a lone
+/// `.o`: every ELF member of the archive is post-processed in place against
+/// the same function list, and the archive is then repacked, preserving
+/// member order (the symbol index is regenerated to match, since fixup can
+/// rename or drop symbols).
+///
+/// Non-ELF members (e.g. a pre-existing `//`/`/` bookkeeping member some
+/// other tool left lying around as a plain member, or a stray text file)
+/// are passed through untouched.
+pub fn fixup_archive(
+    archive_path: &Path,
+    functions: &[Function],
+    asm_prelude: &[u8],
+    assembler: &str,
+    output_enc: &str,
+    drop_mdebug_gptab: bool,
+    drop_comment: bool,
+    convert_statics: &str,
+    symbol_overrides: &HashMap<String, SymbolOverride>,
+    symbol_map: &HashMap<String, u8>,
+    arch: &dyn Arch,
+    split_meta: bool,
+) -> Result<()> {
+    let data = std::fs::read(archive_path)?;
+    let mut archive = crate::ar::Archive::parse(&data)
+        .map_err(|e| ObjFileError::ElfError(e.to_string()))?;
+
+    for member in &mut archive.members {
+        if member.data.len() < 4 || &member.data[0..4] != b"\x7fELF" {
+            continue;
+        }
+
+        let elf = ElfFile::new(&member.data)?;
+        let mut builder = fixup_objfile_data(
+            elf,
+            functions,
+            asm_prelude,
+            assembler,
+            output_enc,
+            drop_mdebug_gptab,
+            drop_comment,
+            convert_statics,
+            symbol_overrides,
+            symbol_map,
+            arch,
+            split_meta,
+        )?;
+
+        // `ElfBuilder::write` is path-based like the rest of this module;
+        // route the in-memory result through a temp file purely to
+        // serialize it.
+        let temp_obj = NamedTempFile::new()?;
+        builder.write(temp_obj.path().to_str()
+            .ok_or_else(|| ObjFileError::Io(io::Error::new(io::ErrorKind::Other, "Invalid temp path")))?)
+            .map_err(ObjFileError::from)?;
+        member.data = std::fs::read(temp_obj.path())?;
+    }
+
+    std::fs::write(archive_path, archive.to_bytes())?;
+    Ok(())
+}
+
+/// Append a `.note.split` section recording, for each injected function
+/// and section-copy record, the name/section/size/original-offset an
+/// external diff tool (objdiff/decomp-toolkit style) needs to match it up
+/// again after the merge shifts addresses and symbol indices around.
+///
+/// The payload is self-contained: a 4-byte `b"SPLT"` magic, a `u32`
+/// version, a `u32` record count, then one fixed-size record per symbol
+/// (`name` as an offset into the trailing string blob, `section` index,
+/// `size`, `orig_offset`), followed by that blob. Everything is packed
+/// through `objfile.fmt` so endianness matches the host object.
+fn write_split_meta(
+    objfile: &mut ElfFile,
+    func_sizes: &HashMap<String, usize>,
+    to_copy: &HashMap<&str, Vec<SectionCopyData>>,
+) -> Result<()> {
+    struct Record {
+        name: String,
+        section: u32,
+        size: u32,
+        orig_offset: u32,
+    }
+
+    let section_index = |name: &str| objfile.find_section(name).map(|s| s.index as u32);
+
+    let mut records = Vec::new();
+    if let Some(text_index) = section_index(".text") {
+        for (name, size) in func_sizes {
+            records.push(Record { name: name.clone(), section: text_index, size: *size as u32, orig_offset: 0 });
+        }
+    }
+    for (sectype, entries) in to_copy {
+        let Some(index) = section_index(sectype) else { continue };
+        for entry in entries {
+            records.push(Record {
+                name: entry.fn_desc.clone(),
+                section: index,
+                size: entry.count as u32,
+                orig_offset: entry.pos as u32,
+            });
+        }
+    }
+
+    // `func_sizes`/`to_copy` iterate in randomized hash order; sort by a
+    // stable key so the emitted `.note.split` payload is byte-identical
+    // across builds, matching the `drop_comment` determinism goal.
+    records.sort_by(|a, b| (&a.name, a.section, a.orig_offset).cmp(&(&b.name, b.section, b.orig_offset)));
+
+    let mut strtab = vec![0u8]; // offset 0 reserved for the empty string
+    let name_offsets: Vec<u32> = records.iter().map(|r| {
+        let offset = strtab.len() as u32;
+        strtab.extend(r.name.bytes());
+        strtab.push(0);
+        offset
+    }).collect();
 
+    let fmt = objfile.fmt;
+    let mut w = fmt.writer();
+    w.push_bytes(b"SPLT");
+    w.push_u32(1); // version
+    w.push_u32(records.len() as u32);
+    for (record, &name_offset) in records.iter().zip(&name_offsets) {
+        w.push_u32(name_offset);
+        w.push_u32(record.section);
+        w.push_u32(record.size);
+        w.push_u32(record.orig_offset);
+    }
+    w.push_bytes(&strtab);
+
+    objfile.add_section(".note.split", SHT_NOTE, 0, 0, 0, 1, 0, w.into_bytes())?;
     Ok(())
 }
 
@@ -417,6 +812,9 @@ fn process_sections(
 fn process_mdebug_symbols(
     objfile: &mut ElfFile,
     convert_statics: &str,
+    symbol_overrides: &HashMap<String, SymbolOverride>,
+    symbol_map: &HashMap<String, u8>,
+    relocated_symbols: &HashSet<Symbol>,
     objfile_name: &str,
 ) -> Result<Vec<Symbol>> {
     let mut new_syms = Vec::new();
@@ -481,17 +879,38 @@ fn process_mdebug_symbols(
                         ))?;
                     
                     let symtype = if sc == 1 { STT_FUNC } else { STT_OBJECT };
-                    let binding = if convert_statics == "global" || convert_statics == "global-with-filename" {
-                        STB_GLOBAL
-                    } else {
-                        STB_LOCAL
+                    let override_ = symbol_overrides.get(&final_name);
+                    let binding = match override_.and_then(|o| o.visibility.as_deref()) {
+                        Some("global") => STB_GLOBAL,
+                        Some("weak") => STB_WEAK,
+                        Some("local") => STB_LOCAL,
+                        // force_active keeps the linker from garbage-collecting an
+                        // otherwise-unreferenced static; the only lever we have for
+                        // that is making it visible outside the translation unit.
+                        None if override_.is_some_and(|o| o.force_active) => STB_GLOBAL,
+                        // `from-map` looks the real binding up in a parsed linker
+                        // map instead of blanket-globalizing; a static absent from
+                        // the map falls back to the same heuristic a map-less build
+                        // uses: local unless something outside this translation
+                        // unit already relocates against it.
+                        _ if convert_statics == "from-map" => symbol_map.get(&final_name).copied()
+                            .unwrap_or_else(|| {
+                                if relocated_symbols.iter().any(|s| s.name == final_name) {
+                                    STB_GLOBAL
+                                } else {
+                                    STB_LOCAL
+                                }
+                            }),
+                        _ if convert_statics == "global" || convert_statics == "global-with-filename" => STB_GLOBAL,
+                        _ => STB_LOCAL,
                     };
-                    
+                    let st_size = override_.and_then(|o| o.size).unwrap_or(0);
+
                     let sym = Symbol::from_parts(
                         &objfile.fmt,
                         strtab_index,
                         value,
-                        0,
+                        st_size,
                         (binding << 4) | symtype,
                         STV_DEFAULT,
                         section.index as u16,
@@ -526,14 +945,17 @@ fn process_mdebug_symbols(
 fn process_symbols(
     objfile: &mut ElfFile,
     convert_statics: &str,
+    symbol_overrides: &HashMap<String, SymbolOverride>,
+    symbol_map: &HashMap<String, u8>,
     all_text_glabels: &HashSet<String>,
     relocated_symbols: &HashSet<Symbol>,
     func_sizes: &HashMap<String, usize>,
     moved_late_rodata: &HashMap<u32, u32>,
+    arch: &dyn Arch,
 ) -> Result<HashSet<Symbol>> {
     let empty_symbol = objfile.symtab.symbol_entries[0].clone();
     let mut new_syms = vec![empty_symbol];
-    
+
     // Add non-temporary symbols from original file
     new_syms.extend(
         objfile.symtab.symbol_entries[1..]
@@ -541,10 +963,13 @@ fn process_symbols(
             .filter(|s| !is_temp_name(&s.name))
             .cloned()
     );
-    
-    // Process mdebug symbols if needed
-    let mut mdebug_syms = process_mdebug_symbols(objfile, convert_statics, objfile.name)?;
-    new_syms.append(&mut mdebug_syms);
+
+    // Process mdebug symbols if needed (only architectures with an
+    // SGI-style `.mdebug` section carry statics here at all)
+    if arch.has_mdebug() {
+        let mut mdebug_syms = process_mdebug_symbols(objfile, convert_statics, symbol_overrides, symbol_map, relocated_symbols, objfile.name)?;
+        new_syms.append(&mut mdebug_syms);
+    }
     
     // Handle duplicate symbols
     new_syms.sort_by_key(|s| (s.st_shndx != SHN_UNDEF, s.name == "_gp_disp"));
@@ -604,16 +1029,19 @@ fn process_relocations(
     modified_text_positions: &HashSet<usize>,
     jtbl_rodata_positions: &HashSet<usize>,
     moved_late_rodata: &HashMap<u32, u32>,
+    arch: &dyn Arch,
 ) -> Result<()> {
+    let known_reloc_types: HashSet<u32> = arch.relocation_types().iter().map(|&(_, ty)| ty).collect();
+
     // Process both REL and RELA sections
     for section in &mut objfile.sections {
         if section.sh_type != SHT_REL && section.sh_type != SHT_RELA {
             continue;
         }
-        
+
         let target_section = objfile.sections.get(section.sh_info as usize)
             .ok_or_else(|| ObjFileError::SectionError("Invalid relocation target section".to_string()))?;
-        
+
         let mut relocs = section.relocs()
             .into_iter()
             .filter(|rel| {
@@ -635,13 +1063,41 @@ fn process_relocations(
                 rel
             })
             .collect::<Vec<_>>();
-        
+
+        for rel in &relocs {
+            if !known_reloc_types.contains(&(rel.rel_type as u32)) {
+                return Err(ObjFileError::RelocationError(format!(
+                    "relocation type {} in section {} is not recognized by --arch {}",
+                    rel.rel_type, target_section.name, arch.name()
+                )));
+            }
+        }
+
         // Sort relocations by offset
         relocs.sort_by_key(|rel| rel.r_offset);
         
         // Update relocation section data
         section.data = relocs.iter().flat_map(|rel| rel.to_bin()).collect();
     }
-    
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write as _;
+
+    #[test]
+    fn test_parse_linker_map_local_global_split() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            "Cross Reference Table\n\nSymbol                                            File\ng_someStatic                                       a.c.o\ng_someShared                                        a.c.o\n                                                     b.c.o\n"
+        ).unwrap();
+
+        let bindings = parse_linker_map(file.path()).unwrap();
+        assert_eq!(bindings.get("g_someStatic"), Some(&STB_LOCAL));
+        assert_eq!(bindings.get("g_someShared"), Some(&STB_GLOBAL));
+    }
+}
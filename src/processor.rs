@@ -4,6 +4,7 @@ use std::path::Path;
 use regex::Regex;
 use lazy_static::lazy_static;
 
+use crate::arch;
 use crate::utils::error::{Error, Result};
 use crate::utils::state::GlobalState;
 use crate::asm::block::GlobalAsmBlock;
@@ -15,6 +16,15 @@ lazy_static! {
     static ref FLOAT_RE: Regex = Regex::new(r"[-+]?[0-9]*\.?[0-9]+([eE][-+]?[0-9]+)?f").unwrap();
 }
 
+/// Resolve a `.incbin`/`.include` path recorded on a [`GlobalAsmBlock`]
+/// against the directory of the file that directive appeared in, so it can
+/// be folded into the `.d` file's dependency list alongside the GLOBAL_ASM
+/// source files themselves.
+fn resolve_incbin_dep(base_file: &Path, dep: &str) -> String {
+    let dir = base_file.parent().unwrap_or_else(|| Path::new(""));
+    dir.join(dep).to_string_lossy().into_owned()
+}
+
 /// Convert a float string to its hexadecimal representation
 fn repl_float_hex(cap: &regex::Captures) -> String {
     let float_str = cap[0].trim().trim_end_matches('f');
@@ -39,32 +49,17 @@ pub fn parse_source<R: BufRead, W: Write>(
     out_dependencies: &mut Vec<String>,
     print_source: Option<&mut W>,
 ) -> Result<Vec<Function>> {
-    // Calculate instruction counts based on optimization level
-    let (min_instr_count, skip_instr_count, prelude_if_late_rodata) = match (opts.opt.as_str(), opts.framepointer) {
-        ("O1" | "O2", true) => (6, 5, 0),
-        ("O1" | "O2", false) => (2, 1, 0),
-        ("O0", true) => (8, 8, 0),
-        ("O0", false) => (4, 4, 0),
-        ("g", true) => (7, 7, 0),
-        ("g", false) => (4, 4, 0),
-        ("g3", true) => (4, 4, 0),
-        ("g3", false) => (2, 2, 0),
-        _ => return Err(Error::InvalidInput("must pass one of -g, -O0, -O1, -O2, -O2 -g3".into())),
-    };
-
-    // Adjust for KPIC
-    let (min_instr_count, skip_instr_count, prelude_if_late_rodata) = if opts.kpic {
-        if opts.opt == "g3" || opts.opt == "O2" {
-            (min_instr_count, skip_instr_count, 3)
-        } else {
-            (min_instr_count + 3, skip_instr_count + 3, prelude_if_late_rodata)
-        }
-    } else {
-        (min_instr_count, skip_instr_count, prelude_if_late_rodata)
-    };
+    // Instruction counts and jump-table placement are architecture-specific;
+    // pull both out of the `--arch` profile instead of hardcoding MIPS here.
+    let profile = arch::from_name(&opts.arch)?;
+    let counts = profile.instr_counts(&opts.opt, opts.framepointer, opts.kpic)?;
+    let (min_instr_count, skip_instr_count, prelude_if_late_rodata) = (
+        counts.min_instr_count,
+        counts.skip_instr_count,
+        counts.prelude_if_late_rodata,
+    );
 
-    let use_jtbl_for_rodata = opts.opt.as_str() == "O2" || opts.opt.as_str() == "g3" 
-        && !opts.framepointer && !opts.kpic;
+    let use_jtbl_for_rodata = profile.use_jtbl_for_rodata(&opts.opt, opts.framepointer, opts.kpic);
 
     let mut state = GlobalState::new(
         min_instr_count,
@@ -86,6 +81,7 @@ pub fn parse_source<R: BufRead, W: Write>(
     while f.read_line(&mut line)? > 0 {
         let raw_line = line.trim_end().to_string();
         let trimmed_line = raw_line.trim_start();
+        let here = |e: Error| e.with_location(opts.filename.display().to_string(), line_no, Some(raw_line.clone()));
 
         // Ensure one output line per source line
         output_lines.push(String::new());
@@ -93,7 +89,10 @@ pub fn parse_source<R: BufRead, W: Write>(
 
         if let Some(ref mut asm_block) = global_asm {
             if trimmed_line.starts_with(')') {
-                let (src, func) = asm_block.clone().finish(&mut state)?;
+                for dep in &asm_block.incbin_deps {
+                    out_dependencies.push(resolve_incbin_dep(&opts.filename, dep));
+                }
+                let (src, func) = asm_block.clone().finish(&mut state).map_err(here)?;
                 let start_index = current_line_idx - src.len() + 1;
                 for (i, line2) in src.into_iter().enumerate() {
                     output_lines[start_index + i] = line2;
@@ -101,7 +100,7 @@ pub fn parse_source<R: BufRead, W: Write>(
                 asm_functions.push(func);
                 global_asm = None;
             } else {
-                asm_block.process_line(&raw_line, &opts.output_enc)?;
+                asm_block.process_line(&raw_line, &opts.output_enc).map_err(here)?;
             }
         } else if trimmed_line == "GLOBAL_ASM(" || trimmed_line == "#pragma GLOBAL_ASM(" {
             global_asm = Some(GlobalAsmBlock::new(&format!("GLOBAL_ASM block at line {}", line_no)));
@@ -127,7 +126,7 @@ pub fn parse_source<R: BufRead, W: Write>(
 
             let mut ext_global_asm = GlobalAsmBlock::new(&fname);
             for line2 in prologue {
-                ext_global_asm.process_line(&line2, &opts.output_enc)?;
+                ext_global_asm.process_line(&line2, &opts.output_enc).map_err(here)?;
             }
 
             match File::open(&fname) {
@@ -135,9 +134,12 @@ pub fn parse_source<R: BufRead, W: Write>(
                     let reader = BufReader::new(file);
                     for line2 in reader.lines() {
                         let line2 = line2?;
-                        ext_global_asm.process_line(&line2, &opts.output_enc)?;
+                        ext_global_asm.process_line(&line2, &opts.output_enc).map_err(here)?;
+                    }
+                    for dep in &ext_global_asm.incbin_deps {
+                        out_dependencies.push(resolve_incbin_dep(Path::new(&fname), dep));
                     }
-                    let (src, func) = ext_global_asm.finish(&mut state)?;
+                    let (src, func) = ext_global_asm.finish(&mut state).map_err(here)?;
                     output_lines[current_line_idx] = src.join("");
                     asm_functions.push(func);
                     out_dependencies.push(fname);
@@ -145,14 +147,14 @@ pub fn parse_source<R: BufRead, W: Write>(
                 Err(e) if e.kind() == io::ErrorKind::NotFound => {
                     output_lines[current_line_idx] = format!("#include \"GLOBAL_ASM:{}\"", fname);
                 }
-                Err(e) => return Err(Error::Io(e)),
+                Err(e) => return Err(here(Error::Io(e))),
             }
         } else if trimmed_line == "#pragma asmproc recurse" {
             is_early_include = true;
         } else if is_early_include {
             is_early_include = false;
             if !trimmed_line.starts_with("#include ") {
-                return Err(Error::InvalidInput("#pragma asmproc recurse must be followed by an #include".into()));
+                return Err(here(Error::InvalidInput("#pragma asmproc recurse must be followed by an #include".into())));
             }
             let include_path = trimmed_line[trimmed_line.find(' ').unwrap() + 2..trimmed_line.len()-1].to_string();
             let fpath = Path::new(&opts.filename).parent().unwrap_or_else(|| Path::new(""));
@@ -160,17 +162,22 @@ pub fn parse_source<R: BufRead, W: Write>(
             out_dependencies.push(fname.to_string_lossy().into_owned());
 
             let mut include_file = File::open(&fname)
-                .map_err(|e| Error::Io(e))?;
+                .map_err(|e| here(Error::Io(e)))?;
             let mut include_src = Vec::new();
+            // Parse the included file with its own path, so errors raised while
+            // walking it (and any further nested includes) report that path
+            // and line number rather than this file's.
+            let mut include_opts = opts.clone();
+            include_opts.filename = fname.clone();
             parse_source(
                 &mut BufReader::new(&mut include_file),
-                opts,
+                &include_opts,
                 out_dependencies,
                 Some(&mut include_src),
             )?;
             writeln!(include_src, "#line {} \"{}\"", line_no + 1, opts.filename.display())?;
             output_lines[current_line_idx] = String::from_utf8(include_src)
-                .map_err(|_| Error::InvalidInput("Invalid UTF-8 in included file".into()))?;
+                .map_err(|_| here(Error::InvalidInput("Invalid UTF-8 in included file".into())))?;
         } else {
             if opts.enable_cutscene_data_float_encoding {
                 if CUTSCENE_DATA_RE.is_match(trimmed_line) {
@@ -192,6 +199,11 @@ pub fn parse_source<R: BufRead, W: Write>(
         line_no += 1;
     }
 
+    if let Some(asm_block) = &global_asm {
+        return Err(Error::AssemblyProcessing(format!("unterminated {}", asm_block.fn_desc))
+            .with_location(opts.filename.display().to_string(), line_no.saturating_sub(1), None));
+    }
+
     if let Some(print_source) = print_source {
         for line in &output_lines {
             write!(print_source, "{}\n", line)?;
@@ -0,0 +1,21 @@
+/// Splice `ASM_PROCESSOR_OPTS` in between the program name and the caller's
+/// own arguments, so persistent defaults set there (e.g.
+/// `--drop-mdebug-gptab --convert-statics global`) apply without editing
+/// every build invocation. The env tokens come first, so for most flags an
+/// explicit one later in `argv` wins (clap takes the last occurrence).
+///
+/// The opt-level flag (`-O2`, `-g`, ...) is the exception: it's a required,
+/// non-multiple `ArgGroup`, so supplying *different* opt flags via the env
+/// and the command line makes clap reject the combination as conflicting
+/// rather than letting the command line override the environment - callers
+/// relying on `ASM_PROCESSOR_OPTS` for a default opt level must not also
+/// pass one explicitly.
+pub fn merge_env_opts(argv: &[String]) -> Vec<String> {
+    let mut merged = Vec::with_capacity(argv.len());
+    merged.extend(argv.first().cloned());
+    if let Ok(opts) = std::env::var("ASM_PROCESSOR_OPTS") {
+        merged.extend(opts.split_whitespace().map(String::from));
+    }
+    merged.extend(argv.iter().skip(1).cloned());
+    merged
+}
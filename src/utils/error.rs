@@ -1,6 +1,24 @@
+use std::fmt;
 use std::io;
 use thiserror::Error;
 
+/// Where in a source file an error occurred: the path of the file being
+/// parsed (which may be an `#include`d file, not the top-level one) and the
+/// 1-based line number within it, plus the offending line's text when
+/// available.
+#[derive(Debug, Clone)]
+pub struct Location {
+    pub path: String,
+    pub line: usize,
+    pub line_text: Option<String>,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.path, self.line)
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("I/O error: {0}")]
@@ -32,6 +50,40 @@ pub enum Error {
 
     #[error("Invalid format: {0}")]
     InvalidFormat(String),
+
+    /// Wraps another error with the source location it occurred at. Produced
+    /// by [`Error::with_location`] as `parse_source` walks a file; printed by
+    /// the CLI as a `path:line: error: ...` compiler-style diagnostic.
+    #[error("{location}: {source}")]
+    WithLocation {
+        #[source]
+        source: Box<Error>,
+        location: Location,
+    },
+}
+
+impl Error {
+    /// Attach source location context to this error, unless it already has
+    /// some (the innermost/first-attached location - typically the one
+    /// closest to where the error actually occurred, e.g. inside an
+    /// `#include`d file - is the most useful one to keep).
+    pub fn with_location(self, path: impl Into<String>, line: usize, line_text: Option<String>) -> Error {
+        if matches!(self, Error::WithLocation { .. }) {
+            return self;
+        }
+        Error::WithLocation {
+            source: Box::new(self),
+            location: Location { path: path.into(), line, line_text },
+        }
+    }
+
+    /// The location attached by [`Error::with_location`], if any.
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            Error::WithLocation { location, .. } => Some(location),
+            _ => None,
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -19,6 +19,8 @@ pub struct Opts {
     pub filename: PathBuf,
     /// Output encoding
     pub output_enc: String,
+    /// Target architecture profile (see `crate::arch::from_name`)
+    pub arch: String,
 }
 
 impl Default for Opts {
@@ -32,6 +34,7 @@ impl Default for Opts {
             pascal: false,
             filename: PathBuf::from("input.c"),
             output_enc: "utf-8".to_string(),
+            arch: "mips".to_string(),
         }
     }
 }
@@ -47,6 +50,7 @@ impl Opts {
         pascal: bool,
         filename: impl Into<PathBuf>,
         output_enc: impl Into<String>,
+        arch: impl Into<String>,
     ) -> Self {
         Self {
             opt: opt.into(),
@@ -57,6 +61,7 @@ impl Opts {
             pascal,
             filename: filename.into(),
             output_enc: output_enc.into(),
+            arch: arch.into(),
         }
     }
 }